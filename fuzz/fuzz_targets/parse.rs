@@ -0,0 +1,28 @@
+//! Parses arbitrary input in the strict and the lenient modes, and as bytes in any encoding
+#![no_main]
+
+use html2md_rs::{
+    parser::{safe_parse_html, safe_parse_html_bytes, safe_parse_html_with_config},
+    structs::ParseConfig,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = safe_parse_html_bytes(data);
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let _ = safe_parse_html(input.to_string());
+    let config = ParseConfig {
+        lenient: true,
+        record_spans: true,
+        ..Default::default()
+    };
+    // the lenient mode only fails when a limit is exceeded
+    if let Err(error) = safe_parse_html_with_config(input.to_string(), &config) {
+        assert!(matches!(
+            error,
+            html2md_rs::parser::ParseHTMLError::LimitExceeded(..)
+        ));
+    }
+});
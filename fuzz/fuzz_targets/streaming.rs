@@ -0,0 +1,29 @@
+//! Feeds arbitrary input to a streaming parser in two chunks, which must parse the same as the whole input
+#![no_main]
+
+use html2md_rs::{
+    parser::{safe_parse_html_with_config, StreamingParser},
+    structs::ParseConfig,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&split, data)) = data.split_first() else {
+        return;
+    };
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let config = ParseConfig {
+        lenient: true,
+        ..Default::default()
+    };
+    let split = usize::from(split).min(data.len());
+    let mut parser = StreamingParser::with_config(config.clone());
+    let streamed = parser
+        .feed(&data[..split])
+        .and_then(|_| parser.feed(&data[split..]))
+        .and_then(|_| parser.finish());
+    let whole = safe_parse_html_with_config(input.to_string(), &config);
+    assert_eq!(streamed.map(|parsed| parsed.root), whole);
+});
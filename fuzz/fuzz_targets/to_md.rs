@@ -0,0 +1,11 @@
+//! Converts arbitrary input to markdown
+#![no_main]
+
+use html2md_rs::to_md::safe_from_html_to_md;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let _ = safe_from_html_to_md(input.to_string());
+    }
+});
@@ -0,0 +1,348 @@
+//! This module contains the Document struct, which stores the nodes of an HTML tree in an arena.
+//!
+//! Unlike the Node struct, whose children are owned by their parent, the nodes of a Document refer to each other by NodeId,
+//! so it is possible to go from a node to its parent, its siblings and its ancestors.
+//! A Document can be converted from and into a Node with the `From` trait.
+
+use crate::structs::{Attributes, Node, NodeType, Span};
+use std::ops::{Index, IndexMut};
+
+/// Identifies a node in a Document, which stays the same as long as the Document exists
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// Returns the index of the node in the arena, which is in the order the nodes were added
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// Represents a node in a Document, which holds its data and the links to the nodes around it
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DocumentNode {
+    pub tag_name: Option<NodeType>,
+    pub value: Option<String>,
+    pub attributes: Option<Attributes>,
+    /// The position of the node in the source HTML, which is only recorded when parsing with `ParseConfig::record_spans`
+    pub span: Option<Span>,
+    parent: Option<NodeId>,
+    first_child: Option<NodeId>,
+    last_child: Option<NodeId>,
+    previous_sibling: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+}
+
+impl DocumentNode {
+    /// Creates a new DocumentNode from tag_name, value and attributes, which is not linked to any other node yet
+    pub fn new(
+        tag_name: Option<NodeType>,
+        value: Option<String>,
+        attributes: Option<Attributes>,
+    ) -> Self {
+        DocumentNode {
+            tag_name,
+            value,
+            attributes,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the parent of the node, which is None for the root
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    /// Returns the first child of the node
+    pub fn first_child(&self) -> Option<NodeId> {
+        self.first_child
+    }
+
+    /// Returns the last child of the node
+    pub fn last_child(&self) -> Option<NodeId> {
+        self.last_child
+    }
+
+    /// Returns the sibling right before the node
+    pub fn previous_sibling(&self) -> Option<NodeId> {
+        self.previous_sibling
+    }
+
+    /// Returns the sibling right after the node
+    pub fn next_sibling(&self) -> Option<NodeId> {
+        self.next_sibling
+    }
+}
+
+/// Represents an HTML tree whose nodes are stored in an arena
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::{document::Document, parser::safe_parse_html, structs::NodeType::*};
+///
+/// let input = "<ul><li><blockquote><p>hello</p></blockquote></li></ul>".to_string();
+/// let document = Document::from(safe_parse_html(input).unwrap());
+///
+/// let text = document
+///     .descendants(document.root())
+///     .find(|&id| document[id].tag_name == Some(Text))
+///     .unwrap();
+/// assert!(document.is_within(text, &[Blockquote]));
+/// assert!(document.is_within(text, &[Ul, Ol]));
+///
+/// let li = document.ancestors(text).nth(2).unwrap();
+/// assert_eq!(document[li].tag_name, Some(Li));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Document {
+    nodes: Vec<DocumentNode>,
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Document::new()
+    }
+}
+
+impl Document {
+    /// Creates a new Document which only has an empty root node
+    pub fn new() -> Self {
+        Document::with_root(DocumentNode::default())
+    }
+
+    /// Creates a new Document with the root node passed in
+    pub fn with_root(root: DocumentNode) -> Self {
+        let root = DocumentNode {
+            parent: None,
+            first_child: None,
+            last_child: None,
+            previous_sibling: None,
+            next_sibling: None,
+            ..root
+        };
+        Document { nodes: vec![root] }
+    }
+
+    /// Returns the id of the root node
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Returns the number of nodes in the Document
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Checks whether the Document only has its root node
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 1
+    }
+
+    /// Returns the node with the id, or None if the id is not from this Document
+    pub fn get(&self, id: NodeId) -> Option<&DocumentNode> {
+        self.nodes.get(id.0)
+    }
+
+    /// Returns a mutable reference to the node with the id, or None if the id is not from this Document
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut DocumentNode> {
+        self.nodes.get_mut(id.0)
+    }
+
+    /// Adds the node as the last child of `parent`, and returns its id
+    ///
+    /// The links of the node passed in are ignored.
+    pub fn append(&mut self, parent: NodeId, node: DocumentNode) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        let previous_sibling = self[parent].last_child;
+        self.nodes.push(DocumentNode {
+            parent: Some(parent),
+            first_child: None,
+            last_child: None,
+            previous_sibling,
+            next_sibling: None,
+            ..node
+        });
+
+        match previous_sibling {
+            Some(previous_sibling) => self[previous_sibling].next_sibling = Some(id),
+            None => self[parent].first_child = Some(id),
+        }
+        self[parent].last_child = Some(id);
+        id
+    }
+
+    /// Adds the Node and all of its descendants as the last child of `parent`, and returns the id of the Node
+    pub fn append_node(&mut self, parent: NodeId, node: Node) -> NodeId {
+        let mut appended = None;
+        // the children are pushed in reverse, so that they are appended in order
+        let mut stack = vec![(parent, node)];
+        while let Some((parent, node)) = stack.pop() {
+            let id = self.append(
+                parent,
+                DocumentNode {
+                    tag_name: node.tag_name,
+                    value: node.value,
+                    attributes: node.attributes,
+                    span: node.span,
+                    ..Default::default()
+                },
+            );
+            appended.get_or_insert(id);
+            stack.extend(node.children.into_iter().rev().map(|child| (id, child)));
+        }
+        appended.unwrap_or(parent)
+    }
+
+    /// Returns the parent of the node
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self[id].parent
+    }
+
+    /// Returns the sibling right before the node
+    pub fn previous_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self[id].previous_sibling
+    }
+
+    /// Returns the sibling right after the node
+    pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self[id].next_sibling
+    }
+
+    /// Returns an iterator over the children of the node, in order
+    pub fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self[id].first_child, move |&child| self[child].next_sibling)
+    }
+
+    /// Returns an iterator over the ancestors of the node, from its parent to the root
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self[id].parent, move |&ancestor| self[ancestor].parent)
+    }
+
+    /// Returns an iterator over the node and its descendants, in the order they appear in the HTML
+    pub fn descendants(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(Some(id), move |&current| {
+            if let Some(first_child) = self[current].first_child {
+                return Some(first_child);
+            }
+            // go up until a node with a next sibling is found, without leaving the subtree of `id`
+            let mut current = current;
+            while current != id {
+                if let Some(next_sibling) = self[current].next_sibling {
+                    return Some(next_sibling);
+                }
+                current = self[current].parent?;
+            }
+            None
+        })
+    }
+
+    /// Checks whether the node is a descendant of any element with one of the tags passed in
+    pub fn is_within(&self, id: NodeId, tags: &[NodeType]) -> bool {
+        self.ancestors(id).any(|ancestor| {
+            self[ancestor]
+                .tag_name
+                .as_ref()
+                .is_some_and(|tag_name| tags.contains(tag_name))
+        })
+    }
+
+    /// Converts the node and its descendants into a Node
+    ///
+    /// The `within_special_tag` of each Node is computed from its ancestors, the same way the parser does.
+    pub fn to_node(&self, id: NodeId) -> Node {
+        let mut root = self.shallow_node(id);
+        root.within_special_tag = self.within_special_tags(id);
+
+        // each entry is a Node being built along with its next child to be added to it
+        let mut stack = vec![(root, self[id].first_child)];
+        let mut built = None;
+        while let Some((node, next_child)) = stack.pop() {
+            match next_child {
+                Some(child) => {
+                    let mut child_node = self.shallow_node(child);
+                    child_node.within_special_tag = within_special_tag_of_child(&node);
+                    stack.push((node, self[child].next_sibling));
+                    stack.push((child_node, self[child].first_child));
+                }
+                None => match stack.last_mut() {
+                    Some((parent, _)) => parent.children.push(node),
+                    None => built = Some(node),
+                },
+            }
+        }
+        built.unwrap_or_default()
+    }
+
+    /// Returns the special tags (i.e. `<ul>`, `<blockquote>`) the node is in, from the outermost one
+    fn within_special_tags(&self, id: NodeId) -> Option<Vec<NodeType>> {
+        let mut tags = self
+            .ancestors(id)
+            .filter_map(|ancestor| self[ancestor].tag_name.clone())
+            .filter(NodeType::is_special_tag)
+            .collect::<Vec<_>>();
+        tags.reverse();
+        (!tags.is_empty()).then_some(tags)
+    }
+
+    /// Returns a Node with the data of the node, without its children
+    fn shallow_node(&self, id: NodeId) -> Node {
+        let node = &self[id];
+        Node {
+            tag_name: node.tag_name.clone(),
+            value: node.value.clone(),
+            attributes: node.attributes.clone(),
+            within_special_tag: None,
+            children: Vec::new(),
+            span: node.span,
+        }
+    }
+}
+
+/// Returns the within_special_tag of a child of the Node
+fn within_special_tag_of_child(parent: &Node) -> Option<Vec<NodeType>> {
+    let mut within_special_tag = parent.within_special_tag.clone();
+    if let Some(tag_name) = parent.tag_name.as_ref().filter(|tag| tag.is_special_tag()) {
+        within_special_tag
+            .get_or_insert_with(Vec::new)
+            .push(tag_name.clone());
+    }
+    within_special_tag
+}
+
+impl Index<NodeId> for Document {
+    type Output = DocumentNode;
+
+    fn index(&self, id: NodeId) -> &DocumentNode {
+        &self.nodes[id.0]
+    }
+}
+
+impl IndexMut<NodeId> for Document {
+    fn index_mut(&mut self, id: NodeId) -> &mut DocumentNode {
+        &mut self.nodes[id.0]
+    }
+}
+
+impl From<Node> for Document {
+    fn from(node: Node) -> Self {
+        let mut document = Document::with_root(DocumentNode {
+            tag_name: node.tag_name,
+            value: node.value,
+            attributes: node.attributes,
+            span: node.span,
+            ..Default::default()
+        });
+        let root = document.root();
+        for child in node.children {
+            document.append_node(root, child);
+        }
+        document
+    }
+}
+
+impl From<Document> for Node {
+    fn from(document: Document) -> Self {
+        document.to_node(document.root())
+    }
+}
@@ -0,0 +1,391 @@
+//! This module contains functions that detect the character encoding of HTML bytes and decode them into a string.
+//!
+//! The encoding is determined following the HTML encoding sniffing algorithm: the byte order mark comes first,
+//! then the `<meta charset>` or `<meta http-equiv="Content-Type">` declared in the first 1024 bytes,
+//! and finally UTF-8 if the bytes are valid UTF-8, or windows-1252 otherwise.
+//! UTF-8, UTF-16 and the single-byte Windows code pages are supported.
+
+mod tables;
+
+use std::borrow::Cow;
+use tables::*;
+
+/// The number of bytes scanned for a `<meta>` declaring the encoding
+const PRESCAN_LENGTH: usize = 1024;
+
+/// Represents a character encoding supported by the library
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows874,
+    Windows1250,
+    Windows1251,
+    Windows1252,
+    Windows1253,
+    Windows1254,
+    Windows1255,
+    Windows1256,
+    Windows1257,
+    Windows1258,
+}
+
+impl Encoding {
+    /// Returns the encoding with the label passed in (i.e. `utf-8`, `latin1`, `cp1251`), following the WHATWG Encoding Standard
+    ///
+    /// Returns None if the label is unknown or the encoding is not supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use html2md_rs::encoding::Encoding;
+    ///
+    /// assert_eq!(Encoding::for_label(" ISO-8859-1 "), Some(Encoding::Windows1252));
+    /// assert_eq!(Encoding::for_label("shift_jis"), None);
+    /// ```
+    pub fn for_label(label: &str) -> Option<Encoding> {
+        use Encoding::*;
+        let label = label
+            .trim_matches(|c: char| c.is_ascii_whitespace())
+            .to_ascii_lowercase();
+        let encoding =
+            match label.as_str() {
+                "unicode-1-1-utf-8" | "unicode11utf8" | "unicode20utf8" | "utf-8" | "utf8"
+                | "x-unicode20utf8" => Utf8,
+                "csunicode" | "iso-10646-ucs-2" | "ucs-2" | "unicode" | "unicodefeff"
+                | "utf-16" | "utf-16le" => Utf16Le,
+                "unicodefffe" | "utf-16be" => Utf16Be,
+                "dos-874" | "iso-8859-11" | "iso8859-11" | "iso885911" | "tis-620"
+                | "windows-874" => Windows874,
+                "cp1250" | "windows-1250" | "x-cp1250" => Windows1250,
+                "cp1251" | "windows-1251" | "x-cp1251" => Windows1251,
+                "ansi_x3.4-1968" | "ascii" | "cp1252" | "cp819" | "csisolatin1" | "ibm819"
+                | "iso-8859-1" | "iso-ir-100" | "iso8859-1" | "iso88591" | "iso_8859-1"
+                | "iso_8859-1:1987" | "l1" | "latin1" | "us-ascii" | "windows-1252"
+                | "x-cp1252" => Windows1252,
+                "cp1253" | "windows-1253" | "x-cp1253" => Windows1253,
+                "cp1254" | "csisolatin5" | "iso-8859-9" | "iso-ir-148" | "iso8859-9"
+                | "iso88599" | "iso_8859-9" | "iso_8859-9:1989" | "l5" | "latin5"
+                | "windows-1254" | "x-cp1254" => Windows1254,
+                "cp1255" | "windows-1255" | "x-cp1255" => Windows1255,
+                "cp1256" | "windows-1256" | "x-cp1256" => Windows1256,
+                "cp1257" | "windows-1257" | "x-cp1257" => Windows1257,
+                "cp1258" | "windows-1258" | "x-cp1258" => Windows1258,
+                _ => return None,
+            };
+        Some(encoding)
+    }
+
+    /// Returns the name of the encoding (i.e. `UTF-8`, `windows-1252`)
+    pub fn name(&self) -> &'static str {
+        use Encoding::*;
+        match self {
+            Utf8 => "UTF-8",
+            Utf16Le => "UTF-16LE",
+            Utf16Be => "UTF-16BE",
+            Windows874 => "windows-874",
+            Windows1250 => "windows-1250",
+            Windows1251 => "windows-1251",
+            Windows1252 => "windows-1252",
+            Windows1253 => "windows-1253",
+            Windows1254 => "windows-1254",
+            Windows1255 => "windows-1255",
+            Windows1256 => "windows-1256",
+            Windows1257 => "windows-1257",
+            Windows1258 => "windows-1258",
+        }
+    }
+
+    /// Decodes the bytes into a string, replacing invalid sequences with U+FFFD
+    ///
+    /// The bytes are borrowed when they are valid UTF-8 and decode to the same string, such as ASCII bytes in a Windows code page.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use html2md_rs::encoding::Encoding;
+    ///
+    /// assert_eq!(Encoding::Windows1252.decode(b"caf\xE9 \x80"), "caf\u{e9} \u{20ac}");
+    /// assert_eq!(Encoding::Utf16Le.decode(b"h\x00i\x00"), "hi");
+    /// ```
+    pub fn decode(self, bytes: &[u8]) -> Cow<'_, str> {
+        use Encoding::*;
+        let table = match self {
+            Utf8 => return String::from_utf8_lossy(bytes),
+            Utf16Le => return Cow::Owned(decode_utf16(bytes, u16::from_le_bytes)),
+            Utf16Be => return Cow::Owned(decode_utf16(bytes, u16::from_be_bytes)),
+            Windows874 => &WINDOWS_874,
+            Windows1250 => &WINDOWS_1250,
+            Windows1251 => &WINDOWS_1251,
+            Windows1252 => &WINDOWS_1252,
+            Windows1253 => &WINDOWS_1253,
+            Windows1254 => &WINDOWS_1254,
+            Windows1255 => &WINDOWS_1255,
+            Windows1256 => &WINDOWS_1256,
+            Windows1257 => &WINDOWS_1257,
+            Windows1258 => &WINDOWS_1258,
+        };
+        match std::str::from_utf8(bytes) {
+            Ok(ascii) if bytes.is_ascii() => Cow::Borrowed(ascii),
+            _ => Cow::Owned(
+                bytes
+                    .iter()
+                    .map(|&byte| match byte {
+                        0..=0x7F => byte as char,
+                        _ => table[byte as usize - 0x80],
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Decodes UTF-16 bytes, replacing unpaired surrogates and a trailing odd byte with U+FFFD
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let chunks = bytes.chunks_exact(2);
+    let odd_byte = !chunks.remainder().is_empty();
+    let mut res = char::decode_utf16(chunks.map(|chunk| from_bytes([chunk[0], chunk[1]])))
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect::<String>();
+    if odd_byte {
+        res.push(char::REPLACEMENT_CHARACTER);
+    }
+    res
+}
+
+/// Determines the encoding of HTML bytes, following the HTML encoding sniffing algorithm
+///
+/// Returns the encoding along with the length of the byte order mark, which should be skipped before decoding.
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::encoding::{sniff_encoding, Encoding};
+///
+/// let input = b"<html><head><meta charset=\"windows-1251\"></head></html>";
+/// assert_eq!(sniff_encoding(input), (Encoding::Windows1251, 0));
+/// assert_eq!(sniff_encoding(b"\xEF\xBB\xBF<p>hello</p>"), (Encoding::Utf8, 3));
+/// assert_eq!(sniff_encoding(b"<p>caf\xE9</p>"), (Encoding::Windows1252, 0));
+/// ```
+pub fn sniff_encoding(bytes: &[u8]) -> (Encoding, usize) {
+    if bytes.starts_with(b"\xEF\xBB\xBF") {
+        return (Encoding::Utf8, 3);
+    }
+    if bytes.starts_with(b"\xFE\xFF") {
+        return (Encoding::Utf16Be, 2);
+    }
+    if bytes.starts_with(b"\xFF\xFE") {
+        return (Encoding::Utf16Le, 2);
+    }
+
+    let encoding = prescan(&bytes[..bytes.len().min(PRESCAN_LENGTH)]).unwrap_or_else(|| {
+        if std::str::from_utf8(bytes).is_ok() {
+            Encoding::Utf8
+        } else {
+            Encoding::Windows1252
+        }
+    });
+    (encoding, 0)
+}
+
+/// Decodes HTML bytes into a string, with the encoding determined by `sniff_encoding`
+///
+/// Returns the decoded string along with the encoding used.
+pub fn decode_html(bytes: &[u8]) -> (Cow<'_, str>, Encoding) {
+    let (encoding, bom_length) = sniff_encoding(bytes);
+    (encoding.decode(&bytes[bom_length..]), encoding)
+}
+
+/// Scans the bytes for a `<meta>` declaring the encoding, skipping comments and the attributes of other tags
+fn prescan(bytes: &[u8]) -> Option<Encoding> {
+    let mut position = 0;
+    while position < bytes.len() {
+        let rest = &bytes[position..];
+        if rest.starts_with(b"<!--") {
+            // the `-->` can share its dashes with the `<!--`
+            let end = find(&rest[2..], b"-->")?;
+            position += 2 + end + 3;
+        } else if starts_with_ignore_case(rest, b"<meta")
+            && rest
+                .get(5)
+                .is_some_and(|&byte| is_whitespace(byte) || byte == b'/')
+        {
+            position += 5;
+            if let Some(encoding) = meta_encoding(bytes, &mut position)? {
+                return Some(encoding);
+            }
+        } else if rest.len() > 1
+            && (rest[1].is_ascii_alphabetic()
+                || (rest[1] == b'/' && rest.get(2).is_some_and(u8::is_ascii_alphabetic)))
+            && rest[0] == b'<'
+        {
+            // skip the tag name and the attributes of the tag
+            position += rest
+                .iter()
+                .position(|&byte| is_whitespace(byte) || byte == b'>')?;
+            while next_attribute(bytes, &mut position)?.is_some() {}
+        } else if rest.starts_with(b"<!") || rest.starts_with(b"</") || rest.starts_with(b"<?") {
+            position += rest.iter().position(|&byte| byte == b'>')? + 1;
+        } else {
+            position += 1;
+        }
+    }
+    None
+}
+
+/// Reads the attributes of a `<meta>` tag starting at `position`, and returns the encoding it declares if there is any
+///
+/// Returns None if the tag is cut off by the end of the bytes.
+fn meta_encoding(bytes: &[u8], position: &mut usize) -> Option<Option<Encoding>> {
+    let mut attribute_names = Vec::new();
+    let mut got_pragma = false;
+    // need_pragma is Some(true) when the charset is from the content attribute, which is only used with http-equiv
+    let mut need_pragma = None;
+    let mut charset = None;
+
+    while let Some((name, value)) = next_attribute(bytes, position)? {
+        if attribute_names.contains(&name) {
+            continue;
+        }
+        match name.as_str() {
+            "http-equiv" => got_pragma |= value.eq_ignore_ascii_case("content-type"),
+            "content" if charset.is_none() => {
+                if let Some(encoding) =
+                    charset_from_content(&value).and_then(|label| meta_label(&label))
+                {
+                    charset = Some(Some(encoding));
+                    need_pragma = Some(true);
+                }
+            }
+            "charset" => {
+                charset = Some(meta_label(&value));
+                need_pragma = Some(false);
+            }
+            _ => (),
+        }
+        attribute_names.push(name);
+    }
+
+    let charset = match need_pragma {
+        None => return Some(None),
+        Some(true) if !got_pragma => return Some(None),
+        _ => charset.flatten(),
+    };
+    // a document declaring UTF-16 in itself can't be UTF-16, since the declaration was read as ASCII
+    Some(charset.map(|charset| match charset {
+        Encoding::Utf16Le | Encoding::Utf16Be => Encoding::Utf8,
+        charset => charset,
+    }))
+}
+
+/// Returns the encoding with the label declared in a `<meta>`, where `x-user-defined` is read as windows-1252
+fn meta_label(label: &str) -> Option<Encoding> {
+    if label.trim().eq_ignore_ascii_case("x-user-defined") {
+        return Some(Encoding::Windows1252);
+    }
+    Encoding::for_label(label)
+}
+
+/// Reads the next attribute of a tag starting at `position`, and returns its lowercase name and its value
+///
+/// Returns Some(None) at the end of the tag, or None if the tag is cut off by the end of the bytes.
+fn next_attribute(bytes: &[u8], position: &mut usize) -> Option<Option<(String, String)>> {
+    skip_while(bytes, position, |byte| is_whitespace(byte) || byte == b'/');
+    if *bytes.get(*position)? == b'>' {
+        return Some(None);
+    }
+
+    let mut name = String::new();
+    loop {
+        let byte = *bytes.get(*position)?;
+        match byte {
+            b'=' if !name.is_empty() => break,
+            b'/' | b'>' => return Some(Some((name, String::new()))),
+            _ if is_whitespace(byte) => break,
+            _ => name.push(byte.to_ascii_lowercase() as char),
+        }
+        *position += 1;
+    }
+
+    skip_while(bytes, position, is_whitespace);
+    if *bytes.get(*position)? != b'=' {
+        return Some(Some((name, String::new())));
+    }
+    *position += 1;
+    skip_while(bytes, position, is_whitespace);
+
+    let mut value = String::new();
+    let quote = *bytes.get(*position)?;
+    if quote == b'"' || quote == b'\'' {
+        *position += 1;
+        loop {
+            let byte = *bytes.get(*position)?;
+            *position += 1;
+            if byte == quote {
+                return Some(Some((name, value)));
+            }
+            value.push(byte.to_ascii_lowercase() as char);
+        }
+    }
+    loop {
+        let byte = *bytes.get(*position)?;
+        if is_whitespace(byte) || byte == b'>' {
+            return Some(Some((name, value)));
+        }
+        value.push(byte.to_ascii_lowercase() as char);
+        *position += 1;
+    }
+}
+
+/// Extracts the charset from the content attribute of a `<meta>` (i.e. `text/html; charset=utf-8`)
+fn charset_from_content(content: &str) -> Option<String> {
+    let mut rest = content;
+    loop {
+        let index = rest.to_ascii_lowercase().find("charset")?;
+        rest = rest[index + 7..].trim_start_matches(|c: char| c.is_ascii_whitespace());
+        if let Some(value) = rest.strip_prefix('=') {
+            rest = value.trim_start_matches(|c: char| c.is_ascii_whitespace());
+            break;
+        }
+    }
+
+    match rest.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let value = &rest[1..];
+            value.find(quote).map(|end| value[..end].to_string())
+        }
+        _ => {
+            let end = rest
+                .find(|c: char| c.is_ascii_whitespace() || c == ';')
+                .unwrap_or(rest.len());
+            (end > 0).then(|| rest[..end].to_string())
+        }
+    }
+}
+
+/// Advances `position` while the byte at it matches the predicate
+fn skip_while(bytes: &[u8], position: &mut usize, predicate: impl Fn(u8) -> bool) {
+    while bytes.get(*position).is_some_and(|&byte| predicate(byte)) {
+        *position += 1;
+    }
+}
+
+/// Returns the index of the first occurrence of `needle` in `haystack`
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Checks whether the bytes start with the ASCII prefix passed in, ignoring case
+fn starts_with_ignore_case(bytes: &[u8], prefix: &[u8]) -> bool {
+    bytes
+        .get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+/// Checks whether the byte is whitespace in the prescan (tab, line feed, form feed, carriage return or space)
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
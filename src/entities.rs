@@ -0,0 +1,218 @@
+//! This module contains functions that decode HTML character references.
+//!
+//! Both named references (i.e. `&amp;`, `&nbsp;`) and numeric references (i.e. `&#8217;`, `&#x1F600;`) are decoded
+//! following the rules of the HTML5 character reference state, including the legacy named references without a trailing semicolon.
+
+mod table;
+
+use std::borrow::Cow;
+use table::NAMED_ENTITIES;
+
+/// The length of the longest name in the named character reference table, including the semicolon
+const LONGEST_ENTITY_NAME: usize = 32;
+
+/// Replacements for the numeric character references in the range 0x80 to 0x9F,
+/// which browsers interpret as windows-1252 code points
+const C1_REPLACEMENTS: [(u32, char); 27] = [
+    (0x80, '\u{20AC}'),
+    (0x82, '\u{201A}'),
+    (0x83, '\u{0192}'),
+    (0x84, '\u{201E}'),
+    (0x85, '\u{2026}'),
+    (0x86, '\u{2020}'),
+    (0x87, '\u{2021}'),
+    (0x88, '\u{02C6}'),
+    (0x89, '\u{2030}'),
+    (0x8A, '\u{0160}'),
+    (0x8B, '\u{2039}'),
+    (0x8C, '\u{0152}'),
+    (0x8E, '\u{017D}'),
+    (0x91, '\u{2018}'),
+    (0x92, '\u{2019}'),
+    (0x93, '\u{201C}'),
+    (0x94, '\u{201D}'),
+    (0x95, '\u{2022}'),
+    (0x96, '\u{2013}'),
+    (0x97, '\u{2014}'),
+    (0x98, '\u{02DC}'),
+    (0x99, '\u{2122}'),
+    (0x9A, '\u{0161}'),
+    (0x9B, '\u{203A}'),
+    (0x9C, '\u{0153}'),
+    (0x9E, '\u{017E}'),
+    (0x9F, '\u{0178}'),
+];
+
+/// Decodes the character references in a text node
+///
+/// Returns the input unchanged (and without allocating) if it does not contain any character reference.
+///
+/// # Arguments
+///
+/// * `input` - A string slice that holds the text to be decoded
+pub(crate) fn decode_text(input: &str) -> Cow<'_, str> {
+    decode(input, false)
+}
+
+/// Decodes the character references in an attribute value
+///
+/// Unlike text, a legacy named reference without a semicolon is left as it is when it is followed by `=` or an alphanumeric character,
+/// so that URLs such as `?a=1&copy=2` are kept intact.
+///
+/// # Arguments
+///
+/// * `input` - A string slice that holds the attribute value to be decoded
+pub(crate) fn decode_attribute(input: &str) -> Cow<'_, str> {
+    decode(input, true)
+}
+
+fn decode(input: &str, in_attribute: bool) -> Cow<'_, str> {
+    let Some(first_ampersand) = input.find('&') else {
+        return Cow::Borrowed(input);
+    };
+
+    let mut res = String::with_capacity(input.len());
+    res.push_str(&input[..first_ampersand]);
+
+    let mut rest = &input[first_ampersand..];
+    while let Some(ampersand_index) = rest.find('&') {
+        res.push_str(&rest[..ampersand_index]);
+        // reference is the string right after the ampersand
+        let reference = &rest[ampersand_index + 1..];
+        let consumed = if reference.starts_with('#') {
+            decode_numeric(reference, &mut res)
+        } else {
+            decode_named(reference, in_attribute, &mut res)
+        };
+        match consumed {
+            Some(consumed) => rest = &reference[consumed..],
+            None => {
+                // not a character reference, so the ampersand is kept as it is
+                res.push('&');
+                rest = reference;
+            }
+        }
+    }
+    res.push_str(rest);
+
+    Cow::Owned(res)
+}
+
+/// Checks whether the text right after an ampersand starts with a character reference, which would be decoded
+///
+/// # Arguments
+///
+/// * `reference` - A string slice that holds the text right after the ampersand
+pub(crate) fn starts_with_reference(reference: &str) -> bool {
+    let mut decoded = String::new();
+    if reference.starts_with('#') {
+        decode_numeric(reference, &mut decoded).is_some()
+    } else {
+        decode_named(reference, false, &mut decoded).is_some()
+    }
+}
+
+/// Decodes a numeric character reference (i.e. `#8217;` or `#x1F600;`) at the start of `reference` into `res`
+///
+/// Returns the number of bytes consumed, or None if `reference` is not a numeric character reference
+fn decode_numeric(reference: &str, res: &mut String) -> Option<usize> {
+    let (radix, digits_start) = match reference.as_bytes().get(1) {
+        Some(b'x') | Some(b'X') => (16, 2),
+        _ => (10, 1),
+    };
+    let digits = &reference[digits_start..];
+    let digits_len = digits
+        .find(|c: char| !c.is_digit(radix))
+        .unwrap_or(digits.len());
+    if digits_len == 0 {
+        return None;
+    }
+
+    // the code point saturates instead of overflowing, anything above 0x10FFFF is invalid anyway
+    let code_point = digits[..digits_len].chars().fold(0u32, |acc, c| {
+        acc.saturating_mul(radix)
+            .saturating_add(c.to_digit(radix).unwrap_or(0))
+    });
+    res.push(numeric_reference_to_char(code_point));
+
+    let mut consumed = digits_start + digits_len;
+    if reference[consumed..].starts_with(';') {
+        consumed += 1;
+    }
+    Some(consumed)
+}
+
+/// Maps the code point of a numeric character reference to the character browsers render
+fn numeric_reference_to_char(code_point: u32) -> char {
+    if code_point == 0 {
+        return '\u{FFFD}';
+    }
+    if let Ok(index) = C1_REPLACEMENTS.binary_search_by_key(&code_point, |(from, _)| *from) {
+        return C1_REPLACEMENTS[index].1;
+    }
+    // char::from_u32 rejects surrogates and code points above 0x10FFFF
+    char::from_u32(code_point).unwrap_or('\u{FFFD}')
+}
+
+/// Decodes a named character reference (i.e. `amp;` or the legacy `amp`) at the start of `reference` into `res`
+///
+/// Returns the number of bytes consumed, or None if `reference` does not start with a known name
+fn decode_named(reference: &str, in_attribute: bool, res: &mut String) -> Option<usize> {
+    let name_len = reference
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(reference.len());
+    if name_len == 0 {
+        return None;
+    }
+    let candidate_len = if reference[name_len..].starts_with(';') {
+        name_len + 1
+    } else {
+        name_len
+    };
+
+    // find the longest name in the table that the reference starts with
+    // the candidate only contains ASCII characters, so slicing by any length is safe
+    let (matched_len, value) = (1..=candidate_len.min(LONGEST_ENTITY_NAME))
+        .rev()
+        .find_map(|len| {
+            NAMED_ENTITIES
+                .binary_search_by_key(&&reference[..len], |(name, _)| *name)
+                .ok()
+                .map(|index| (len, NAMED_ENTITIES[index].1))
+        })?;
+
+    if in_attribute && !reference[..matched_len].ends_with(';') {
+        // for historical reasons, legacy references in attributes followed by `=` or an alphanumeric are not decoded
+        if let Some(next) = reference[matched_len..].chars().next() {
+            if next == '=' || next.is_ascii_alphanumeric() {
+                return None;
+            }
+        }
+    }
+
+    res.push_str(value);
+    Some(matched_len)
+}
+
+#[test]
+fn decodes_named_and_numeric_references() {
+    assert_eq!(
+        decode_text("&amp; &lt; &nbsp; &#8217; &#x1F600;"),
+        "& < \u{a0} \u{2019} \u{1F600}"
+    );
+    assert_eq!(decode_text("&copy 2024 &notit;"), "\u{a9} 2024 \u{ac}it;");
+    assert_eq!(
+        decode_text("&#0; &#x110000; &#128; &#xD800;"),
+        "\u{FFFD} \u{FFFD} \u{20AC} \u{FFFD}"
+    );
+    assert_eq!(
+        decode_text("AT&T &unknown; &# &#x;"),
+        "AT&T &unknown; &# &#x;"
+    );
+    assert!(starts_with_reference("amp; b") && starts_with_reference("#x41"));
+    assert!(!starts_with_reference("T and") && !starts_with_reference("#;"));
+    assert_eq!(
+        decode_attribute("?a=1&copy=2&amp=3&lt;"),
+        "?a=1&copy=2&amp=3<"
+    );
+}
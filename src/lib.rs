@@ -20,6 +20,14 @@
 //!
 //! By default, the library parses all attributes of an HTML element as a HashMap.
 //!
+//! ## HTML Output
+//!
+//! A parsed Node can be serialized back to HTML with the [to_html](to_html/index.html) module.
+//!
+//! ## Selectors
+//!
+//! The nodes of a tree can be found or removed with CSS selectors (i.e. `nav, footer, .ads`) with the [selector](selector/index.html) module.
+//!
 //! ## Markdown Convention
 //!
 //! This library follows the [CommonMark Spec](https://spec.commonmark.org/0.31.2/).
@@ -28,6 +36,11 @@
 //!
 //! This library is licensed under the MIT license. Check the [GitHub repository](https://github.com/izyuumi/html2md-rs) for more information.
 
+pub mod document;
+pub mod encoding;
+mod entities;
 pub mod parser;
+pub mod selector;
 pub mod structs;
+pub mod to_html;
 pub mod to_md;
@@ -1,93 +1,384 @@
 //! This module contains functions which parsees HTML string into a custom Node struct.
 //!
 //! The Node struct is used to represent the HTML elements and their children in a tree-like structure.
+//! The whitespace of texts is collapsed the way browsers render it, except inside `<pre>` and `<textarea>`.
+//! The elements inside `<svg>` and `<math>` are parsed as foreign elements (`NodeType::Foreign`), keeping the case of their names (i.e. `viewBox`).
 //!
 //! With the `safe_parse_html` function, malformed HTML will return an error instead of panicking.
+//! The `safe_` functions never panic on any input, which is checked by the property tests and the fuzz targets in `fuzz/`.
 //! The `parse_html` function is a wrapper around `safe_parse_html` that panics if the input is malformed. However, it is deprecated and will be removed in future versions.
+//! The `StreamingParser` struct parses the same way incrementally, from chunks of bytes or a reader.
+//! The `safe_parse_html_bytes` function detects the encoding of HTML bytes, such as windows-1252 or UTF-16, and decodes them before parsing, returning the encoding used.
+//! The `safe_parse_html_fragment` function parses a snippet of HTML as the content of a context element, such as `<li>` elements in a `<ul>`.
+//! The `safe_parse_html_borrowed` function parses into a BorrowedNode, which borrows the texts from the input instead of copying them.
 
-use crate::structs::{
-    AttributeValues, Attributes, Node,
-    NodeType::{self, *},
+mod foreign;
+mod streaming;
+mod tree_builder;
+mod whitespace;
+
+use crate::{
+    encoding::{decode_html, Encoding},
+    entities::{decode_attribute, decode_text},
+    structs::{
+        AttributeValues, Attributes, BorrowedNode, Doctype, DuplicateAttributePolicy, GenericNode,
+        Namespace, Node, NodeType, ParseConfig, ParsedHtml, SourcePosition, Span,
+    },
 };
-use std::{collections::VecDeque, fmt::Display};
+use std::{borrow::Cow, fmt::Display};
+pub use streaming::{safe_parse_html_reader, StreamingParser};
+use tree_builder::{TextValue, TreeBuilder};
 
 /// Errors that will be returned when parsing malformed HTML tags
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum MalformedTagError {
-    /// The closing bracket of the tag is missing
-    MissingClosingBracket(u32),
-    /// The tag name is missing
-    MissingTagName(u32),
+    /// The closing bracket of the tag starting at the position is missing
+    MissingClosingBracket(SourcePosition),
+    /// The tag starting at the position has no name
+    MissingTagName(SourcePosition),
+}
+
+impl MalformedTagError {
+    /// Returns the position in the source HTML where the error occurred
+    pub fn position(&self) -> SourcePosition {
+        match self {
+            MalformedTagError::MissingClosingBracket(position)
+            | MalformedTagError::MissingTagName(position) => *position,
+        }
+    }
+}
+
+impl Display for MalformedTagError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        ParseWarning::from(self).fmt(f)
+    }
 }
 
+impl std::error::Error for MalformedTagError {}
+
 /// Errors that will be returned when parsing malformed HTML attributes
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum MalformedAttributeError {
-    /// The quotation mark of the attribute is missing
-    MissingQuotationMark(u32),
-    /// The attribute name is missing
-    MissingAttributeName(u32),
-    /// The attribute value is missing
-    MissingAttributeValue(u32),
+    /// The closing quotation mark of the value starting at the position is missing
+    MissingQuotationMark(SourcePosition),
+    /// The attribute name is missing before the equal sign at the position
+    MissingAttributeName(SourcePosition),
+    /// The attribute value is missing at the position
+    MissingAttributeValue(SourcePosition),
+    /// The attribute at the position has the same name as an earlier attribute of the tag,
+    /// which is an error with `DuplicateAttributePolicy::Error`
+    DuplicateAttribute(SourcePosition),
 }
 
+impl MalformedAttributeError {
+    /// Returns the position in the source HTML where the error occurred
+    pub fn position(&self) -> SourcePosition {
+        match self {
+            MalformedAttributeError::MissingQuotationMark(position)
+            | MalformedAttributeError::MissingAttributeName(position)
+            | MalformedAttributeError::MissingAttributeValue(position)
+            | MalformedAttributeError::DuplicateAttribute(position) => *position,
+        }
+    }
+}
+
+impl Display for MalformedAttributeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        ParseWarning::from(self).fmt(f)
+    }
+}
+
+impl std::error::Error for MalformedAttributeError {}
+
+/// Errors that will be returned when the HTML exceeds one of the `Limits` of the parser
+///
+/// In the lenient mode, the limits are clamped instead: the elements nested too deep and the attributes over the limit are skipped,
+/// and the parsing stops at the input size or the number of nodes over the limit, keeping the tree parsed before it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LimitExceededError {
+    /// The input is larger than `Limits::max_input_size`, which is reached at the position
+    InputSize(SourcePosition),
+    /// The element at the position is nested deeper than `Limits::max_depth`
+    Depth(SourcePosition),
+    /// The node at the position is over `Limits::max_nodes`
+    NodeCount(SourcePosition),
+    /// The attribute at the position is over `Limits::max_attributes` of its element
+    AttributeCount(SourcePosition),
+}
+
+impl LimitExceededError {
+    /// Returns the position in the source HTML where the limit was exceeded
+    pub fn position(&self) -> SourcePosition {
+        match self {
+            LimitExceededError::InputSize(position)
+            | LimitExceededError::Depth(position)
+            | LimitExceededError::NodeCount(position)
+            | LimitExceededError::AttributeCount(position) => *position,
+        }
+    }
+
+    /// Returns the description of the limit that was exceeded
+    pub fn message(&self) -> &'static str {
+        match self {
+            LimitExceededError::InputSize(_) => "Input too large",
+            LimitExceededError::Depth(_) => "Elements nested too deep",
+            LimitExceededError::NodeCount(_) => "Too many nodes",
+            LimitExceededError::AttributeCount(_) => "Too many attributes",
+        }
+    }
+}
+
+impl Display for LimitExceededError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let position = self.position();
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message(),
+            position.line,
+            position.column
+        )
+    }
+}
+
+impl std::error::Error for LimitExceededError {}
+
 /// Errors that can occur when parsing HTML
-#[derive(Debug, PartialEq, Eq)]
+///
+/// The string is a snippet of the source HTML starting at the position of the error,
+/// which is cut at the end of the line and is at most 40 characters long.
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum ParseHTMLError {
     /// The tag is malformed
     MalformedTag(String, MalformedTagError),
     /// The attribute is malformed
     MalformedAttribute(String, MalformedAttributeError),
+    /// The HTML exceeds a limit of the parser
+    LimitExceeded(String, LimitExceededError),
+}
+
+/// The maximum number of characters in the snippet of an error
+const SNIPPET_MAX_LENGTH: usize = 40;
+/// The maximum number of characters of the source line shown before the error by `ParseHTMLError::render`
+const RENDER_CONTEXT_LENGTH: usize = 40;
+
+impl ParseHTMLError {
+    /// Returns the snippet of the source HTML starting at the position of the error
+    pub fn snippet(&self) -> &str {
+        match self {
+            ParseHTMLError::MalformedTag(snippet, _)
+            | ParseHTMLError::MalformedAttribute(snippet, _)
+            | ParseHTMLError::LimitExceeded(snippet, _) => snippet,
+        }
+    }
+
+    /// Returns the position in the source HTML where the error occurred
+    pub fn position(&self) -> SourcePosition {
+        match self {
+            ParseHTMLError::MalformedTag(_, error) => error.position(),
+            ParseHTMLError::MalformedAttribute(_, error) => error.position(),
+            ParseHTMLError::LimitExceeded(_, error) => error.position(),
+        }
+    }
+
+    /// Returns the span of the snippet in the source HTML
+    pub fn span(&self) -> Span {
+        let start = self.position();
+        let snippet = self.snippet();
+        Span {
+            start,
+            end: SourcePosition {
+                offset: start.offset + snippet.len(),
+                line: start.line,
+                column: start.column + snippet.chars().count(),
+            },
+        }
+    }
+
+    /// Renders the error with the line of the source HTML where it occurred, pointing at the snippet with carets
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - A string slice that holds the HTML which failed to be parsed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use html2md_rs::parser::safe_parse_html;
+    ///
+    /// let input = "<p>hello</p>\n<p>world</p><div".to_string();
+    /// let error = safe_parse_html(input.clone()).unwrap_err();
+    ///
+    /// let expected = "\
+    /// Malformed tag: <div - Missing closing bracket at line 2, column 13
+    ///   |
+    /// 2 | <p>world</p><div
+    ///   |             ^^^^";
+    /// assert_eq!(error.render(&input), expected);
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let position = self.position();
+        let line = source
+            .lines()
+            .nth(position.line.saturating_sub(1))
+            .unwrap_or_default();
+        // long lines are cut so that the rendering stays bounded
+        let skipped = position.column.saturating_sub(RENDER_CONTEXT_LENGTH + 1);
+        let shown: String = line
+            .chars()
+            .skip(skipped)
+            .take(RENDER_CONTEXT_LENGTH + SNIPPET_MAX_LENGTH)
+            .collect();
+        // tabs are kept in the padding so that the carets line up with the line
+        let padding: String = shown
+            .chars()
+            .take(position.column.saturating_sub(skipped + 1))
+            .map(|char| if char == '\t' { '\t' } else { ' ' })
+            .collect();
+        let carets = "^".repeat(self.snippet().chars().count().max(1));
+        let line_number = position.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        format!(
+            "{}\n{} |\n{} | {}\n{} | {}{}",
+            self, gutter, line_number, shown, gutter, padding, carets
+        )
+    }
 }
 
 impl Display for ParseHTMLError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ParseHTMLError::MalformedTag(tag, error) => match error {
-                MalformedTagError::MissingClosingBracket(index) => {
-                    write!(
-                        f,
-                        "Malformed tag: {} - Missing closing bracket at around index {}",
-                        tag, index
-                    )
-                }
-                MalformedTagError::MissingTagName(index) => {
-                    write!(
-                        f,
-                        "Malformed tag: {} - Missing tag name at around index {}",
-                        tag, index
-                    )
-                }
-            },
-            ParseHTMLError::MalformedAttribute(attr, error) => match error {
-                MalformedAttributeError::MissingQuotationMark(index) => {
-                    write!(
-                        f,
-                        "Malformed attribute: {} - Missing quotation mark at around index {}",
-                        attr, index
-                    )
-                }
-                MalformedAttributeError::MissingAttributeName(index) => {
-                    write!(
-                        f,
-                        "Malformed attribute: {} - Missing attribute name at around index {}",
-                        attr, index
-                    )
-                }
-                MalformedAttributeError::MissingAttributeValue(index) => {
-                    write!(
-                        f,
-                        "Malformed attribute: {} - Missing attribute value at around index {}",
-                        attr, index
-                    )
-                }
+            ParseHTMLError::MalformedTag(tag, error) => {
+                write!(f, "Malformed tag: {} - {}", tag, error)
+            }
+            ParseHTMLError::MalformedAttribute(attr, error) => {
+                write!(f, "Malformed attribute: {} - {}", attr, error)
+            }
+            ParseHTMLError::LimitExceeded(snippet, error) => {
+                write!(f, "Limit exceeded: {} - {}", snippet, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseHTMLError {}
+
+/// The kinds of malformed HTML that are recovered from when parsing in the lenient mode
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ParseWarningKind {
+    /// The closing bracket of a tag, comment or DOCTYPE is missing, so it is read up to the end of the input
+    MissingClosingBracket,
+    /// A less-than sign is not followed by a tag name, so it is read as a text
+    MissingTagName,
+    /// The closing quotation mark of an attribute value is missing, so it is read up to the end of the tag
+    MissingQuotationMark,
+    /// An attribute starts with an equal sign, which is read as a part of its name
+    MissingAttributeName,
+    /// The attribute value is missing
+    MissingAttributeValue,
+    /// The attribute has the same name as an earlier attribute, so the first one is kept
+    DuplicateAttribute,
+    /// The HTML exceeds a limit of the parser, which is recorded only where each limit is first exceeded
+    LimitExceeded,
+}
+
+impl ParseWarningKind {
+    /// Returns the description of the kind of warning
+    pub fn message(&self) -> &'static str {
+        match self {
+            ParseWarningKind::MissingClosingBracket => "Missing closing bracket",
+            ParseWarningKind::MissingTagName => "Missing tag name",
+            ParseWarningKind::MissingQuotationMark => "Missing quotation mark",
+            ParseWarningKind::MissingAttributeName => "Missing attribute name",
+            ParseWarningKind::MissingAttributeValue => "Missing attribute value",
+            ParseWarningKind::DuplicateAttribute => "Duplicate attribute",
+            ParseWarningKind::LimitExceeded => "Limit exceeded",
+        }
+    }
+}
+
+/// A malformed part of the HTML that was recovered from when parsing in the lenient mode
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ParseWarning {
+    /// What is malformed
+    pub kind: ParseWarningKind,
+    /// Where the malformed part starts in the source HTML
+    pub position: SourcePosition,
+}
+
+impl From<&MalformedTagError> for ParseWarning {
+    fn from(error: &MalformedTagError) -> Self {
+        let kind = match error {
+            MalformedTagError::MissingClosingBracket(_) => ParseWarningKind::MissingClosingBracket,
+            MalformedTagError::MissingTagName(_) => ParseWarningKind::MissingTagName,
+        };
+        ParseWarning {
+            kind,
+            position: error.position(),
+        }
+    }
+}
+
+impl From<&MalformedAttributeError> for ParseWarning {
+    fn from(error: &MalformedAttributeError) -> Self {
+        let kind = match error {
+            MalformedAttributeError::MissingQuotationMark(_) => {
+                ParseWarningKind::MissingQuotationMark
+            }
+            MalformedAttributeError::MissingAttributeName(_) => {
+                ParseWarningKind::MissingAttributeName
+            }
+            MalformedAttributeError::MissingAttributeValue(_) => {
+                ParseWarningKind::MissingAttributeValue
+            }
+            MalformedAttributeError::DuplicateAttribute(_) => ParseWarningKind::DuplicateAttribute,
+        };
+        ParseWarning {
+            kind,
+            position: error.position(),
+        }
+    }
+}
+
+impl From<&ParseHTMLError> for ParseWarning {
+    fn from(error: &ParseHTMLError) -> Self {
+        match error {
+            ParseHTMLError::MalformedTag(_, error) => ParseWarning::from(error),
+            ParseHTMLError::MalformedAttribute(_, error) => ParseWarning::from(error),
+            ParseHTMLError::LimitExceeded(_, error) => ParseWarning {
+                kind: ParseWarningKind::LimitExceeded,
+                position: error.position(),
             },
         }
     }
 }
 
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.kind.message(),
+            self.position.line,
+            self.position.column
+        )
+    }
+}
+
+/// Returns the bounded snippet of the source at the start of `rest`, which ends at the end of the line
+fn snippet(rest: &str) -> String {
+    rest.lines()
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .take(SNIPPET_MAX_LENGTH)
+        .collect()
+}
+
 /// Safely parses a string of HTML into a Node struct
 ///
+/// It never panics: any input results in either a Node or a ParseHTMLError.
+///
 /// # Arguments
 ///
 /// * `input` - A string slice that holds the HTML to be parsed
@@ -116,264 +407,1078 @@ impl Display for ParseHTMLError {
 ///         attributes: None,
 ///         within_special_tag: None,
 ///         children: Vec::new(),
+///         span: None,
 ///     }],
+///     span: None,
 /// };
 ///
 /// assert_eq!(parsed, Ok(expected));
 /// ```
 pub fn safe_parse_html(input: String) -> Result<Node, ParseHTMLError> {
-    // current_index is the index of the current character being processed
-    let mut current_index = 0;
-    // nodes is a vector of nodes that will be returned as an attribute of the resulting node
-    let mut nodes = Vec::new();
-    // stack is a LIFO stack of nodes that are being processed
-    let mut stack: Vec<Node> = Vec::new();
-
-    while current_index < input.len() {
-        let rest = &input[current_index..];
-        if rest.starts_with("<!") {
-            // if the current character is an exclamation mark, it's a comment or DOCTYPE
-            if rest.starts_with("<!DOCTYPE") {
-                // if the comment is a DOCTYPE, ignore it
-                current_index += rest.find('>').unwrap() + 1;
-                continue;
-            }
-            // find the closing comment tag
-            if let Some(closing_comment_index) = rest.find("-->") {
-                // if the closing comment tag is found, the comment is valid
-                // extract the comment from the rest
-                let comment = &rest[..closing_comment_index + 3];
-                // create a new node with the comment
-                let new_node = Node {
-                    tag_name: Some(Comment),
-                    value: Some(
-                        comment
-                            .trim_start_matches("<!")
-                            .trim_start_matches("--")
-                            .trim_end_matches("-->")
-                            .to_string(),
-                    ),
-                    attributes: None,
-                    within_special_tag: None,
-                    children: Vec::new(),
+    safe_parse_html_with_config(input, &ParseConfig::default())
+}
+
+/// Safely parses a string of HTML into a Node struct with a custom configuration
+///
+/// # Arguments
+///
+/// * `input` - A string slice that holds the HTML to be parsed
+/// * `config` - A reference to a ParseConfig struct that holds the configuration
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::{parser::safe_parse_html_with_config, structs::ParseConfig};
+///
+/// let input = "<div>\n  hello\n</div>".to_string();
+/// let config = ParseConfig {
+///     record_spans: true,
+///     ..Default::default()
+/// };
+/// let parsed = safe_parse_html_with_config(input, &config).unwrap();
+///
+/// let text_span = parsed.children[0].span.unwrap();
+/// assert_eq!(text_span.start.offset, 5);
+/// assert_eq!((text_span.end.line, text_span.end.column), (3, 1));
+/// ```
+pub fn safe_parse_html_with_config(
+    input: String,
+    config: &ParseConfig,
+) -> Result<Node, ParseHTMLError> {
+    safe_parse_html_document_with_config(input, config).map(|parsed| parsed.root)
+}
+
+/// Parses a string of HTML into a Node struct without failing, recovering from malformed HTML the way browsers do
+///
+/// Returns the Node along with the warnings about the malformed parts of the input.
+///
+/// # Arguments
+///
+/// * `input` - A string slice that holds the HTML to be parsed
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::{parser::{parse_html_lenient, ParseWarningKind}, to_md::to_md};
+///
+/// let input = "<p>1 < 2</p><a href=\"https://example.com".to_string();
+/// let (parsed, warnings) = parse_html_lenient(input);
+///
+/// assert_eq!(to_md(parsed), "1 < 2\n");
+/// assert_eq!(warnings[0].kind, ParseWarningKind::MissingTagName);
+/// assert_eq!(warnings[1].kind, ParseWarningKind::MissingClosingBracket);
+/// assert_eq!(warnings[1].position.column, 13);
+/// ```
+pub fn parse_html_lenient(input: String) -> (Node, Vec<ParseWarning>) {
+    let config = ParseConfig {
+        lenient: true,
+        ..Default::default()
+    };
+    // malformed HTML and exceeded limits are recorded as warnings in the lenient mode, so the parsing never fails
+    safe_parse_html_document_with_config(input, &config).map_or_else(
+        |error| (Node::default(), vec![ParseWarning::from(&error)]),
+        |parsed| (parsed.root, parsed.warnings),
+    )
+}
+
+/// Safely parses a string of HTML into a ParsedHtml struct, which holds the resulting Node along with the DOCTYPE of the document
+///
+/// # Arguments
+///
+/// * `input` - A string slice that holds the HTML to be parsed
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::{parser::safe_parse_html_document, structs::QuirksMode};
+///
+/// let input = "<!doctype html><p>hello</p>".to_string();
+/// let parsed = safe_parse_html_document(input).unwrap();
+///
+/// let doctype = parsed.doctype.as_ref().unwrap();
+/// assert_eq!(doctype.name, Some("html".to_string()));
+/// assert_eq!(parsed.quirks_mode(), QuirksMode::NoQuirks);
+/// ```
+pub fn safe_parse_html_document(input: String) -> Result<ParsedHtml, ParseHTMLError> {
+    safe_parse_html_document_with_config(input, &ParseConfig::default())
+}
+
+/// Safely parses a string of HTML into a ParsedHtml struct with a custom configuration
+///
+/// # Arguments
+///
+/// * `input` - A string slice that holds the HTML to be parsed
+/// * `config` - A reference to a ParseConfig struct that holds the configuration
+pub fn safe_parse_html_document_with_config(
+    input: String,
+    config: &ParseConfig,
+) -> Result<ParsedHtml, ParseHTMLError> {
+    let mut state = ParseState::new(config.clone());
+    let parsed = state.parse(&input, true)?;
+    Ok(state.finish(&input[parsed..]))
+}
+
+/// Safely parses a string of HTML into a BorrowedNode struct, whose texts and comments borrow from the input
+///
+/// Only the texts with character references (i.e. `&amp;`) are decoded into owned strings,
+/// so parsing allocates much less than `safe_parse_html`. Use `BorrowedNode::into_owned` to convert the result into a Node.
+///
+/// # Arguments
+///
+/// * `input` - A string slice that holds the HTML to be parsed
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::parser::{safe_parse_html, safe_parse_html_borrowed};
+/// use std::borrow::Cow;
+///
+/// let input = "<p>Tom &amp; Jerry</p><p>hello</p>";
+/// let parsed = safe_parse_html_borrowed(input).unwrap();
+///
+/// assert!(matches!(parsed.children[0].children[0].value, Some(Cow::Owned(_))));
+/// assert!(matches!(parsed.children[1].children[0].value, Some(Cow::Borrowed("hello"))));
+/// assert_eq!(parsed.into_owned(), safe_parse_html(input.to_string()).unwrap());
+/// ```
+pub fn safe_parse_html_borrowed(input: &str) -> Result<BorrowedNode<'_>, ParseHTMLError> {
+    safe_parse_html_borrowed_with_config(input, &ParseConfig::default())
+}
+
+/// Safely parses a string of HTML into a BorrowedNode struct with a custom configuration
+///
+/// # Arguments
+///
+/// * `input` - A string slice that holds the HTML to be parsed
+/// * `config` - A reference to a ParseConfig struct that holds the configuration
+pub fn safe_parse_html_borrowed_with_config<'a>(
+    input: &'a str,
+    config: &ParseConfig,
+) -> Result<BorrowedNode<'a>, ParseHTMLError> {
+    let mut state = ParseState::new(config.clone());
+    let parsed = state.parse(input, true)?;
+    Ok(state.finish(&input[parsed..]).root)
+}
+
+/// Safely parses a fragment of HTML as the content of a context element (i.e. `<li>` elements in a `<ul>`)
+///
+/// Returns the context element with the nodes of the fragment as its children,
+/// which are built as if the fragment were inside the context element (i.e. `within_special_tag` includes `Ul` for a `<ul>` context).
+/// The fragment can't close the context element, and the content of a raw text context element (i.e. `<textarea>`) is read as a text.
+///
+/// # Arguments
+///
+/// * `input` - A string slice that holds the HTML fragment to be parsed
+/// * `context` - The type of the element the fragment belongs in
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::{parser::safe_parse_html_fragment, structs::NodeType, to_md::to_md};
+///
+/// let input = "<li>hello</li><li>world</li></ul>".to_string();
+/// let parsed = safe_parse_html_fragment(input, NodeType::Ol).unwrap();
+///
+/// assert_eq!(parsed.children.len(), 2);
+/// assert_eq!(to_md(parsed), "1. hello\n2. world\n");
+/// ```
+pub fn safe_parse_html_fragment(input: String, context: NodeType) -> Result<Node, ParseHTMLError> {
+    safe_parse_html_fragment_with_config(input, context, &ParseConfig::default())
+}
+
+/// Safely parses a fragment of HTML as the content of a context element with a custom configuration
+///
+/// # Arguments
+///
+/// * `input` - A string slice that holds the HTML fragment to be parsed
+/// * `context` - The type of the element the fragment belongs in
+/// * `config` - A reference to a ParseConfig struct that holds the configuration
+pub fn safe_parse_html_fragment_with_config(
+    input: String,
+    context: NodeType,
+    config: &ParseConfig,
+) -> Result<Node, ParseHTMLError> {
+    let raw_text_kind = RawTextKind::from_node_type(&context);
+    let mut state = ParseState::with_context(config.clone(), context);
+    let parsed = match raw_text_kind {
+        // without its start tag, the end tag of a raw text element is never matched, so the whole fragment is its content
+        Some(raw_text_kind) => {
+            let text = &input[..state.check_input_size(&input)?];
+            if !text.is_empty() {
+                let span = state.span(&input, 0, text.len());
+                let text = match raw_text_kind {
+                    RawTextKind::Escapable => decode_text(text).into_owned(),
+                    _ => text.to_string(),
                 };
-                // add the new_node to the stack
-                nodes.push(new_node);
-                // increment the current_index by the closing_comment_index + 3
-                // and continue to the next iteration
-                current_index += closing_comment_index + 3;
-                continue;
+                state.tree_builder.insert_raw_text(text, span);
             }
-            // if the closing comment tag is not found, the comment is malformed
-            return Err(ParseHTMLError::MalformedTag(
-                rest.to_string(),
-                MalformedTagError::MissingClosingBracket(current_index as u32),
-            ));
-        }
-
-        if rest.starts_with('<') {
-            if let Some(mut closing_index) = find_closing_bracket_index(rest) {
-                // if the tag is a self-closing tag (i.e. <tag_name ... />)
-                let self_closing = if rest.chars().nth(closing_index - 1) == Some('/') {
-                    // if the last character right before the closing bracket is a forward slash, the tag is self-closing
-                    // closing_index is the index of the closing bracket, so decrement it to ignore the forward slash
-                    closing_index -= 1;
-                    true
-                } else {
-                    // if the last character right before the closing bracket is not a forward slash, the tag is not self-closing
-                    false
-                };
+            input.len()
+        }
+        None => state.parse(&input, true)?,
+    };
+    Ok(state.finish(&input[parsed..]).root)
+}
+
+/// Safely parses HTML bytes in any supported encoding into a Node struct
+///
+/// The encoding is determined from the byte order mark, then the `<meta charset>` in the first 1024 bytes,
+/// and falls back to UTF-8, or windows-1252 if the bytes are not valid UTF-8. See `encoding::sniff_encoding` for details.
+/// Returns the Node along with the encoding used, which is the fallback when the declared charset is not supported
+/// (i.e. `<meta charset="shift_jis">`), so that the caller can tell whether the bytes were decoded as declared.
+///
+/// # Arguments
+///
+/// * `input` - The bytes of the HTML to be parsed
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::{
+///     encoding::Encoding,
+///     parser::{safe_parse_html, safe_parse_html_bytes},
+/// };
+///
+/// let input = b"<meta charset=\"windows-1252\"><p>caf\xE9</p>";
+/// let (parsed, encoding) = safe_parse_html_bytes(input).unwrap();
+///
+/// let expected = safe_parse_html("<meta charset=\"windows-1252\"><p>caf\u{e9}</p>".to_string());
+/// assert_eq!(parsed, expected.unwrap());
+/// assert_eq!(encoding, Encoding::Windows1252);
+/// ```
+pub fn safe_parse_html_bytes(input: &[u8]) -> Result<(Node, Encoding), ParseHTMLError> {
+    safe_parse_html_bytes_with_config(input, &ParseConfig::default())
+}
 
-                // the tag content is the string between the opening and closing brackets
-                let tag_content = &rest[1..closing_index];
-
-                // initialize the node name and attribute map
-                let node_name;
-                let mut attribute_map = None;
-                if let Some(space_index) = tag_content.find(|c: char| c.is_whitespace()) {
-                    // if the tag contains a space, split the tag into the node name and attributes
-                    // space_index is the index of the first spce
-                    // node_name is the tag name (i.e. <tag_name ...>)
-                    node_name = &tag_content[..space_index];
-                    // attributes is the string after the first space before the closing bracket
-                    let attributes = &tag_content[space_index..];
-                    // parse the attribute string into a map
-                    match parse_tag_attributes(attributes, current_index) {
-                        Ok(map) => attribute_map = map,
-                        Err(err) => return Err(err),
+/// Safely parses HTML bytes in any supported encoding into a Node struct with a custom configuration
+///
+/// Returns the Node along with the encoding used, like `safe_parse_html_bytes`.
+///
+/// # Arguments
+///
+/// * `input` - The bytes of the HTML to be parsed
+/// * `config` - A reference to a ParseConfig struct that holds the configuration
+pub fn safe_parse_html_bytes_with_config(
+    input: &[u8],
+    config: &ParseConfig,
+) -> Result<(Node, Encoding), ParseHTMLError> {
+    let (decoded, encoding) = decode_html(input);
+    safe_parse_html_with_config(decoded.into_owned(), config).map(|node| (node, encoding))
+}
+
+/// The state of parsing, which is kept between the chunks of the input when parsing incrementally
+#[derive(Debug)]
+pub(crate) struct ParseState<V> {
+    config: ParseConfig,
+    /// tree_builder builds the resulting node from the tags and texts found in the input
+    tree_builder: TreeBuilder<V>,
+    /// doctype is the first DOCTYPE found in the input
+    doctype: Option<Doctype>,
+    /// warnings are the malformed parts of the input recovered from in the lenient mode
+    warnings: Vec<ParseWarning>,
+    /// positions converts the byte offsets into lines and columns for spans and errors, from the start of the chunk being parsed
+    positions: PositionTracker,
+    /// resume is where the search for the end of the token left unparsed by the last chunk stopped
+    resume: Resume,
+    /// exceeded_limits are the limits already recorded as warnings, which are recorded only once in the lenient mode
+    exceeded_limits: Vec<std::mem::Discriminant<LimitExceededError>>,
+    /// stopped is whether the parsing stopped at a limit in the lenient mode, after which the rest of the input is ignored
+    stopped: bool,
+}
+
+impl<V: TextValue> ParseState<V> {
+    pub(crate) fn new(config: ParseConfig) -> Self {
+        ParseState {
+            config,
+            tree_builder: TreeBuilder::new(),
+            doctype: None,
+            warnings: Vec::new(),
+            positions: PositionTracker::default(),
+            resume: Resume::default(),
+            exceeded_limits: Vec::new(),
+            stopped: false,
+        }
+    }
+
+    /// Creates a new ParseState for a fragment, whose nodes are added to the context element
+    pub(crate) fn with_context(config: ParseConfig, context: NodeType) -> Self {
+        ParseState {
+            tree_builder: TreeBuilder::with_context(context),
+            ..ParseState::new(config)
+        }
+    }
+
+    /// Parses the tags and texts in `input`, which continues from the end of the previously parsed input
+    ///
+    /// Unless `is_complete` is true, a tag or text which might continue in the next chunk is left unparsed.
+    /// Returns the number of bytes parsed, from which the next chunk should continue.
+    pub(crate) fn parse<'a>(
+        &mut self,
+        input: &'a str,
+        is_complete: bool,
+    ) -> Result<usize, ParseHTMLError>
+    where
+        V: From<Cow<'a, str>>,
+    {
+        if self.stopped {
+            return Ok(input.len());
+        }
+        let within_limit = self.check_input_size(input)?;
+        if within_limit < input.len() {
+            // the input past the limit is ignored in the lenient mode, so the part before it is parsed as the end of the input
+            self.parse(&input[..within_limit], true)?;
+            self.stopped = true;
+            return Ok(input.len());
+        }
+
+        // current_index is the index of the current character being processed
+        let mut current_index = 0;
+        // token_start is the index of the tag or text processed last, where the limits of the tree are checked
+        let mut token_start = 0;
+
+        while current_index < input.len() {
+            self.check_tree_limits(input, token_start)?;
+            if self.stopped {
+                current_index = input.len();
+                break;
+            }
+            token_start = current_index;
+            // the search for the end of the token left unparsed by the last chunk continues where it stopped
+            let resume = std::mem::take(&mut self.resume);
+
+            let rest = &input[current_index..];
+            if !is_complete && (rest == "<" || rest == "</") {
+                // whether the less-than sign starts a tag is unknown until the next character
+                break;
+            }
+            if rest.starts_with("<!") || rest.starts_with("<?") {
+                // if the current character is an exclamation or question mark, it's a comment, DOCTYPE, CDATA section or processing instruction
+                if rest
+                    .get(..DOCTYPE_PREFIX.len())
+                    .is_some_and(|prefix| prefix.eq_ignore_ascii_case(DOCTYPE_PREFIX))
+                {
+                    // the DOCTYPE ends at the first closing bracket, even inside of a quoted identifier
+                    let from = resume.index(rest, 0);
+                    let Some(closing_index) = rest[from..].find('>').map(|index| index + from)
+                    else {
+                        if !is_complete {
+                            self.resume.scanned = rest.len();
+                            break;
+                        }
+                        let error = self.malformed_tag(
+                            input,
+                            current_index,
+                            MalformedTagError::MissingClosingBracket,
+                        );
+                        self.report(error)?;
+                        // browsers read a DOCTYPE cut off by the end of the input in quirks mode
+                        if self.doctype.is_none() {
+                            let mut cut_off = parse_doctype(&rest[DOCTYPE_PREFIX.len()..]);
+                            cut_off.force_quirks = true;
+                            self.doctype = Some(cut_off);
+                        }
+                        return Ok(input.len());
+                    };
+                    if self.doctype.is_none() {
+                        self.doctype =
+                            Some(parse_doctype(&rest[DOCTYPE_PREFIX.len()..closing_index]));
                     }
-                } else {
-                    // if the tag doesn't contain a space, the tag is the node name
-                    node_name = tag_content;
+                    current_index += closing_index + 1;
+                    continue;
                 }
-
-                if node_name.is_empty() {
-                    // if the tag name is empty, the tag is malformed
-                    return Err(ParseHTMLError::MalformedTag(
-                        tag_content.to_string(),
-                        MalformedTagError::MissingTagName(current_index as u32),
-                    ));
+                let (declaration, length) = match parse_markup_declaration(rest, resume) {
+                    Some(found) => found,
+                    None if !is_complete => {
+                        self.resume.scanned = rest.len();
+                        break;
+                    }
+                    None => {
+                        // if the end of the declaration is not found, it is malformed
+                        let error = self.malformed_tag(
+                            input,
+                            current_index,
+                            MalformedTagError::MissingClosingBracket,
+                        );
+                        self.report(error)?;
+                        // browsers read the declaration up to the end of the input
+                        (unterminated_markup_declaration(rest), rest.len())
+                    }
+                };
+                let span = self.span(input, current_index, current_index + length);
+                match declaration {
+                    MarkupDeclaration::Comment(comment) => self
+                        .tree_builder
+                        .insert_comment(V::from(Cow::Borrowed(comment)), span),
+                    MarkupDeclaration::CData(text) => {
+                        if !text.is_empty() {
+                            self.tree_builder
+                                .insert_text(V::from(Cow::Borrowed(text)), span)
+                        }
+                    }
+                    // processing instructions (i.e. <?xml version="1.0"?>) have no content to render
+                    MarkupDeclaration::ProcessingInstruction => (),
                 }
+                // continue to the next iteration right after the declaration
+                current_index += length;
+                continue;
+            }
 
+            if self.config.lenient && rest.starts_with('<') && !starts_tag_name(rest) {
                 if rest.starts_with("</") {
-                    // if the tag is a closing tag, pop the last node from the stack and add it to the parent
-                    match stack.pop() {
-                        Some(last_node) => {
-                            if stack.is_empty() {
-                                // if the stack is empty, the last node is the root node
-                                nodes.push(last_node);
-                            } else {
-                                let parent = stack.last_mut().unwrap(); // stack is not empty, so unwrap is safe
-                                parent.children.push(last_node);
-                            }
-                            current_index += closing_index + 1;
-                            continue;
-                        }
-                        None => {
-                            // if there is nothing in the stack, the tag is malformed
-                            let closing_bracket_of_closing_tag = rest.find('>');
-                            return Err(ParseHTMLError::MalformedTag(
-                                if let Some(index) = closing_bracket_of_closing_tag {
-                                    // if there is a closing bracket, return the tag with the error
-                                    rest[..index + 1].to_string()
-                                } else {
-                                    rest.to_string()
-                                },
-                                MalformedTagError::MissingClosingBracket(current_index as u32),
-                            ));
+                    // a closing tag without a name is ignored (i.e. `</>`), or read as a comment (i.e. `</ p>`) up to the closing bracket
+                    // content_end is the end of the comment, which is the end of the input if the closing bracket is missing
+                    let from = resume.index(rest, 0);
+                    let (content_end, tag_end) = match rest[from..].find('>') {
+                        Some(closing_index) => (from + closing_index, from + closing_index + 1),
+                        None if !is_complete => {
+                            self.resume.scanned = rest.len();
+                            break;
                         }
+                        None => (rest.len(), rest.len()),
+                    };
+                    let error =
+                        self.malformed_tag(input, current_index, MalformedTagError::MissingTagName);
+                    self.report(error)?;
+                    if content_end > 2 {
+                        let span = self.span(input, current_index, current_index + tag_end);
+                        self.tree_builder
+                            .insert_comment(V::from(Cow::Borrowed(&rest[2..content_end])), span);
                     }
+                    current_index += tag_end;
+                    continue;
                 }
+                // browsers don't read a less-than sign without a tag name right after it as a tag
+                // the less-than sign is read as a part of the text below (i.e. `a < b`)
+                if !is_complete && !rest[resume.index(rest, 0).max(1)..].contains('<') {
+                    // the text might continue in the next chunk, so the warning is reported when it is complete
+                    self.resume.scanned = rest.len();
+                    break;
+                }
+                let error =
+                    self.malformed_tag(input, current_index, MalformedTagError::MissingTagName);
+                self.report(error)?;
+            }
 
-                // parse thae tag name into a NodeType from the node_name string
-                let node_type = NodeType::from_tag_str(node_name);
-
-                // initialize a new node with the tag name and attribute map
-                let mut new_node = Node {
-                    tag_name: Some(node_type.clone()),
-                    value: None,
-                    attributes: attribute_map,
-                    within_special_tag: None,
-                    children: Vec::new(),
+            if rest.starts_with('<') && (!self.config.lenient || starts_tag_name(rest)) {
+                let bracket = match resume.bracket {
+                    Some(state) => (resume.scanned, state),
+                    // the tag name starts after the less-than sign, or the forward slash of a closing tag
+                    None => (
+                        usize::from(rest.starts_with("</")) + 1,
+                        BracketState::TagName,
+                    ),
                 };
+                let closing = find_closing_bracket_index(rest, bracket);
+                if let Ok(closing_index) = closing {
+                    // tag_end is the index right after the closing bracket
+                    let tag_end = closing_index + 1;
 
-                if self_closing {
-                    // if the tag is self-closing, add the node to the parent
-                    // if a parent does not exist, add the node to the nodes vector
-                    if let Some(parent) = stack.last_mut() {
-                        modify_node_with_parent(&mut new_node, parent);
-                        parent.children.push(new_node);
-                    } else {
-                        nodes.push(new_node);
+                    // the tag content is the string between the opening and closing brackets
+                    let tag_content = &rest[1..closing_index];
+
+                    // the node name is the tag name (i.e. <tag_name ...>) up to the first whitespace or forward slash,
+                    // after the forward slash of a closing tag
+                    let name_start = usize::from(rest.starts_with("</"));
+                    let space_index = tag_content[name_start..]
+                        .find(|c: char| c.is_whitespace() || c == '/')
+                        .map_or(tag_content.len(), |index| index + name_start);
+                    let node_name = &tag_content[..space_index];
+
+                    if node_name.is_empty() {
+                        // if the tag name is empty, the tag is malformed
+                        // this only happens in the strict mode, since the lenient mode reads such a tag as a text
+                        return Err(self.malformed_tag(
+                            input,
+                            current_index,
+                            MalformedTagError::MissingTagName,
+                        ));
+                    }
+
+                    if rest.starts_with("</") {
+                        // if the tag is a closing tag, close the open element with the same name
+                        let span = self.span(input, current_index, current_index + tag_end);
+                        self.tree_builder
+                            .close_element(&NodeType::from_tag_str(&node_name[1..]), span);
+                        current_index += tag_end;
+                        continue;
+                    }
+
+                    // parse thae tag name into a NodeType from the node_name string
+                    let node_type = NodeType::from_tag_str(node_name);
+
+                    // the attributes are the string after the node name before the closing bracket
+                    // the tag is self-closing (i.e. <tag_name ... />) if it ends with a forward slash outside of the attribute values
+                    // the warnings are taken back if the tag is read again with the next chunk
+                    let warning_count = self.warnings.len();
+                    let (attribute_map, self_closing) = self.parse_tag_attributes(
+                        &node_type,
+                        &tag_content[space_index..],
+                        input,
+                        current_index + 1 + space_index,
+                    )?;
+                    // the tags of raw text elements are normal tags in foreign content (i.e. <style> in <svg>)
+                    let raw_text_kind = RawTextKind::from_node_type(&node_type).filter(|_| {
+                        !self_closing
+                            && self.tree_builder.namespace_for(&node_type, None) == Namespace::Html
+                    });
+
+                    // the content of raw text elements (i.e. <script>) is read as a text up to the closing tag
+                    // content_end is the index of the end of the content right after the tag
+                    let content_end = match raw_text_kind {
+                        Some(raw_text_kind) => {
+                            let content = &rest[tag_end..];
+                            let content_end = match raw_text_kind {
+                                RawTextKind::Plaintext => None,
+                                _ => {
+                                    let scanned = resume.scanned.saturating_sub(tag_end);
+                                    find_raw_text_end(content, node_name, scanned)
+                                }
+                            };
+                            match content_end {
+                                Some(content_end) => content_end,
+                                // the content might continue in the next chunk
+                                None if !is_complete => {
+                                    self.warnings.truncate(warning_count);
+                                    self.resume.scanned = rest.len();
+                                    break;
+                                }
+                                None => content.len(),
+                            }
+                        }
+                        None => 0,
+                    };
+
+                    // initialize a new node with the tag name and attribute map
+                    let new_node = GenericNode {
+                        tag_name: Some(node_type),
+                        value: None,
+                        attributes: attribute_map,
+                        within_special_tag: None,
+                        children: Vec::new(),
+                        span: self.span(input, current_index, current_index + tag_end),
+                    };
+
+                    // add the new_node to the current node
+                    // unless the tag is self-closing or a void element (i.e. <br>), it becomes the current node
+                    self.tree_builder.insert_element(new_node, self_closing);
+                    current_index += tag_end;
+
+                    if let Some(raw_text_kind) = raw_text_kind {
+                        let content = &input[current_index..current_index + content_end];
+                        if !content.is_empty() {
+                            let span = self.span(input, current_index, current_index + content_end);
+                            self.tree_builder.insert_raw_text(
+                                match raw_text_kind {
+                                    RawTextKind::Escapable => V::from(decode_text(content)),
+                                    _ => V::from(Cow::Borrowed(content)),
+                                },
+                                span,
+                            );
+                        }
+                        current_index += content_end;
                     }
-                    // because the tag is self-closing, increment the current_index by the closing_index + 2
-                    // and continute to the next iteration
-                    current_index += closing_index + 2;
                     continue;
+                } else if !is_complete {
+                    // the tag might be closed in the next chunk
+                    self.resume = Resume {
+                        scanned: rest.len(),
+                        bracket: closing.err(),
+                    };
+                    break;
+                } else {
+                    // if a closing bracket is not found, the tag is malformed
+                    let error = self.malformed_tag(
+                        input,
+                        current_index,
+                        MalformedTagError::MissingClosingBracket,
+                    );
+                    self.report(error)?;
+                    // browsers drop a tag cut off by the end of the input
+                    return Ok(input.len());
                 }
-                // if the tag is not self-closing
-                // add the new_node to the stack
-                if let Some(parent) = stack.last_mut() {
-                    modify_node_with_parent(&mut new_node, parent);
+            }
+
+            // if the current character is not a '<', it's just a text
+            // if an opening bracket is not found, the rest is the content of the text
+            // else, anything upto the opening bracket is the content of the text
+            // in the lenient mode, the text can start with a stray '<' (i.e. `< b`), so it is skipped when finding the next one
+            let text_start = resume
+                .index(rest, 0)
+                .max(usize::from(rest.starts_with('<')));
+            let next_opening_tag = match rest[text_start..].find('<') {
+                Some(index) => index + text_start,
+                // the text might continue in the next chunk
+                None if !is_complete => {
+                    self.resume.scanned = rest.len();
+                    break;
                 }
-                stack.push(new_node);
-                // because the tag is not self-closing, increment the current_index by the closing_index + 1
-                current_index += closing_index + 1;
+                None => rest.len(),
+            };
+            let text = &rest[..next_opening_tag];
+            if text.is_empty() {
                 continue;
-            } else {
-                // if a closing bracket is not found, the tag is malformed
-                return Err(ParseHTMLError::MalformedTag(
-                    rest.to_string(),
-                    MalformedTagError::MissingClosingBracket(current_index as u32),
-                ));
             }
+
+            // add the text to the current node
+            let span = self.span(input, current_index, current_index + next_opening_tag);
+            self.tree_builder
+                .insert_text(V::from(decode_text(text)), span);
+
+            current_index += next_opening_tag
         }
 
-        // if the current character is not a '<', it's just a text
-        // if an opening bracket is not found, the rest is the content of the text
-        // else, anything upto the opening bracket is the content of the text
-        let next_opening_tag = rest.find('<').unwrap_or(rest.len());
-        let text = &rest[..next_opening_tag];
-        if text.trim().is_empty() {
-            // if text is empty or only whitespace, ignore it
-            // increment the current_index by next_opening_tag and continue to the next iteration
-            current_index += next_opening_tag;
-            continue;
-        }
-
-        // initialize new_node as text with the content of the text
-        let new_node = Node {
-            tag_name: Some(Text),
-            value: Some(text.to_string()),
-            attributes: None,
-            within_special_tag: None,
-            children: Vec::new(),
-        };
+        self.check_tree_limits(input, token_start)?;
 
-        // add the new_node to the stack
-        modify_stack_with_node(&mut stack, new_node);
+        // the parsed part is dropped by the caller, so the positions after it are tracked from its end
+        self.positions.advance(input, current_index);
+        Ok(current_index)
+    }
 
-        current_index += next_opening_tag
+    /// Returns the length of the part of `input` within the limit of the input size,
+    /// or an error in the strict mode if the input parsed so far, including `input`, is larger than the limit
+    pub(crate) fn check_input_size(&mut self, input: &str) -> Result<usize, ParseHTMLError> {
+        let max_input_size = self.config.limits.max_input_size;
+        let base = self.positions.start.offset;
+        if base.saturating_add(input.len()) <= max_input_size {
+            return Ok(input.len());
+        }
+        let index = floor_char_boundary(input, max_input_size.saturating_sub(base));
+        let error = self.limit_exceeded(input, index, LimitExceededError::InputSize);
+        self.report_limit(error)?;
+        Ok(index)
     }
 
-    // if the stack is not empty, add the stack to the nodes vector
-    if !stack.is_empty() {
-        for stack_node in stack.drain(..) {
-            nodes.push(stack_node);
+    /// Returns an error in the strict mode if the tree has more nodes or deeper elements than the limits,
+    /// positioned at `index` of the tag or text that was inserted last
+    ///
+    /// In the lenient mode, the elements nested too deep are skipped, keeping their children,
+    /// and the parsing stops once there are too many nodes.
+    fn check_tree_limits(&mut self, input: &str, index: usize) -> Result<(), ParseHTMLError> {
+        let limits = self.config.limits;
+        if self.tree_builder.depth() > limits.max_depth {
+            let error = self.limit_exceeded(input, index, LimitExceededError::Depth);
+            self.report_limit(error)?;
+            self.tree_builder
+                .skip_elements_deeper_than(limits.max_depth);
         }
+        if self.tree_builder.node_count() > limits.max_nodes {
+            let error = self.limit_exceeded(input, index, LimitExceededError::NodeCount);
+            self.report_limit(error)?;
+            self.stopped = true;
+        }
+        Ok(())
     }
 
-    if nodes.len() == 1 {
-        return Ok(nodes.remove(0));
+    /// Closes all of the open elements and returns the result
+    ///
+    /// `rest` is the part of the input which was not parsed, which is expected to be empty.
+    pub(crate) fn finish(mut self, rest: &str) -> ParsedHtml<V> {
+        let span = self.config.record_spans.then(|| Span {
+            start: SourcePosition {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+            end: self.position(rest, rest.len()),
+        });
+        ParsedHtml {
+            root: self.tree_builder.finish(span),
+            doctype: self.doctype,
+            warnings: self.warnings,
+        }
     }
 
-    Ok(Node {
-        tag_name: None,
-        value: None,
-        attributes: None,
-        within_special_tag: None,
-        children: nodes,
-    })
+    /// Returns the error in the strict mode, or records it as a warning in the lenient mode
+    fn report(&mut self, error: ParseHTMLError) -> Result<(), ParseHTMLError> {
+        if !self.config.lenient {
+            return Err(error);
+        }
+        self.warnings.push(ParseWarning::from(&error));
+        Ok(())
+    }
+
+    /// Returns the exceeded limit in the strict mode, or records it as a warning in the lenient mode
+    ///
+    /// Each limit is recorded only where it is first exceeded, so that the warnings don't grow with the input skipped by it.
+    fn report_limit(&mut self, error: ParseHTMLError) -> Result<(), ParseHTMLError> {
+        if let ParseHTMLError::LimitExceeded(_, limit) = &error {
+            let limit = std::mem::discriminant(limit);
+            if self.config.lenient && self.exceeded_limits.contains(&limit) {
+                return Ok(());
+            }
+            self.exceeded_limits.push(limit);
+        }
+        self.report(error)
+    }
+
+    /// Returns the position of the byte at `index` of the chunk being parsed
+    fn position(&mut self, input: &str, index: usize) -> SourcePosition {
+        self.positions.position(input, index)
+    }
+
+    /// Returns the span of the bytes from `start` to `end` of the chunk being parsed, if the spans are recorded
+    fn span(&mut self, input: &str, start: usize, end: usize) -> Option<Span> {
+        self.config.record_spans.then(|| Span {
+            start: self.position(input, start),
+            end: self.position(input, end),
+        })
+    }
+
+    /// Creates a MalformedTag error for the tag at `index` of the chunk being parsed
+    fn malformed_tag(
+        &mut self,
+        input: &str,
+        index: usize,
+        error: fn(SourcePosition) -> MalformedTagError,
+    ) -> ParseHTMLError {
+        ParseHTMLError::MalformedTag(snippet(&input[index..]), error(self.position(input, index)))
+    }
+
+    /// Creates a MalformedAttribute error for the attribute at `index` of the chunk being parsed
+    fn malformed_attribute(
+        &mut self,
+        input: &str,
+        index: usize,
+        error: fn(SourcePosition) -> MalformedAttributeError,
+    ) -> ParseHTMLError {
+        ParseHTMLError::MalformedAttribute(
+            snippet(&input[index..]),
+            error(self.position(input, index)),
+        )
+    }
+
+    /// Returns a limit exceeded error at `index` of the chunk being parsed
+    fn limit_exceeded(
+        &mut self,
+        input: &str,
+        index: usize,
+        error: fn(SourcePosition) -> LimitExceededError,
+    ) -> ParseHTMLError {
+        ParseHTMLError::LimitExceeded(snippet(&input[index..]), error(self.position(input, index)))
+    }
+
+    /// Adds the current attribute to the attribute map following the duplicate attribute policy, and clears the current key and value
+    ///
+    /// An attribute without a value (i.e. `<input disabled>`) is added as `true`.
+    /// `name_start` is the byte offset of the attribute name in the input, where a duplicate attribute is reported.
+    fn add_attribute(
+        &mut self,
+        attribute_map: &mut Attributes,
+        current_key: &mut String,
+        current_value: Option<&mut String>,
+        node_type: &NodeType,
+        input: &str,
+        name_start: usize,
+    ) -> Result<(), ParseHTMLError> {
+        let key = std::mem::take(current_key);
+        let value = match current_value {
+            Some(value) => coerce_attribute_value(
+                node_type,
+                &key,
+                decode_attribute(&std::mem::take(value)).into_owned(),
+            ),
+            None => AttributeValues::from(true),
+        };
+        if key.is_empty() {
+            return Ok(());
+        }
+        if attribute_map.contains(&key) {
+            match self.config.duplicate_attributes {
+                DuplicateAttributePolicy::FirstWins => return Ok(()),
+                DuplicateAttributePolicy::LastWins => (),
+                DuplicateAttributePolicy::Error => {
+                    let error = self.malformed_attribute(
+                        input,
+                        name_start,
+                        MalformedAttributeError::DuplicateAttribute,
+                    );
+                    // the first attribute is kept when recovering, like browsers do
+                    return self.report(error);
+                }
+            }
+        }
+        if attribute_map.len() >= self.config.limits.max_attributes {
+            let error = self.limit_exceeded(input, name_start, LimitExceededError::AttributeCount);
+            // the attributes over the limit are skipped when recovering
+            return self.report_limit(error);
+        }
+        attribute_map.insert(key, value);
+        Ok(())
+    }
 }
 
-/// Adds a new node to the stack with respect to the parent node's special tag and tag type
+/// Converts byte offsets of the input into positions with lines and columns
 ///
-/// # Arguments
-///
-/// * `stack` - A mutable reference to a vector of nodes
-/// * `new_node` - A mutable reference to a node to be added to the stack
-fn modify_stack_with_node(stack: &mut Vec<Node>, mut new_node: Node) {
-    if let Some(parent) = stack.last_mut() {
-        // if the stack is not empty, add new_node to the parent
-        // modify the new_node with the parent's within_special_tag and tag type
-        modify_node_with_parent(&mut new_node, parent);
-        parent.children.push(new_node.clone());
-        return;
+/// The offsets are expected to be increasing, so that the input is scanned only once.
+#[derive(Debug)]
+struct PositionTracker {
+    /// the position of the start of the chunk being parsed in the whole input
+    start: SourcePosition,
+    /// the last position that was converted
+    last: Option<SourcePosition>,
+}
+
+impl Default for PositionTracker {
+    fn default() -> Self {
+        PositionTracker {
+            start: SourcePosition {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+            last: None,
+        }
     }
-    // if stack is empty, add new_node to the stack
-    stack.push(new_node.clone());
 }
 
-/// Modifies a node with the parent's within_special_tag and tag type
+impl PositionTracker {
+    /// Returns the position of the byte at `index` of `input`, which is the chunk starting at `self.start`
+    fn position(&mut self, input: &str, index: usize) -> SourcePosition {
+        let base = self.start.offset;
+        let mut position = match self.last {
+            Some(last) if (base..=base + index).contains(&last.offset) => last,
+            // the chunk is scanned from its start again when an offset before the last one is requested
+            _ => self.start,
+        };
+        for char in input[position.offset - base..index].chars() {
+            if char == '\n' {
+                position.line += 1;
+                position.column = 1;
+            } else {
+                position.column += 1;
+            }
+        }
+        position.offset = base + index;
+        self.last = Some(position);
+        position
+    }
+
+    /// Moves the start of the chunk to the byte at `index` of `input`, where the next chunk continues
+    fn advance(&mut self, input: &str, index: usize) {
+        self.start = self.position(input, index);
+    }
+}
+
+/// Where the search for the end of the token left unparsed by the last chunk stopped,
+/// so that a long token split into many chunks (i.e. a comment) is not scanned from its start for every chunk
 ///
-/// # Arguments
+/// The token starts at the start of the next chunk, since the buffer of a StreamingParser only grows at its end.
+#[derive(Debug, Default, Clone, Copy)]
+struct Resume {
+    /// the number of bytes of the token that were searched without finding its end
+    scanned: usize,
+    /// the state of the search for the closing bracket of a tag, if the tag was not closed
+    bracket: Option<BracketState>,
+}
+
+impl Resume {
+    /// Returns the index of `rest` from which the search for a terminator up to `overlap + 1` bytes long continues,
+    /// going back `overlap` bytes in case the terminator was cut off by the end of the last chunk
+    fn index(&self, rest: &str, overlap: usize) -> usize {
+        floor_char_boundary(rest, self.scanned.saturating_sub(overlap).min(rest.len()))
+    }
+}
+
+/// Returns the largest index of `input` up to `index` which is on a character boundary
+fn floor_char_boundary(input: &str, mut index: usize) -> usize {
+    while !input.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// The start of a DOCTYPE, which is matched case-insensitively
+const DOCTYPE_PREFIX: &str = "<!DOCTYPE";
+
+/// Parses the content of a DOCTYPE (i.e. ` html PUBLIC "..." "..."` in `<!DOCTYPE html PUBLIC "..." "...">`)
 ///
-/// * `node` - A mutable reference to a Node to be modified
-/// * `parent` - A reference to the parent Node
-fn modify_node_with_parent(node: &mut Node, parent: &Node) {
-    if parent.within_special_tag.is_some() {
-        node.within_special_tag
-            .clone_from(&parent.within_special_tag)
-    }
-    if let Some(parent_tag_name) = &parent.tag_name {
-        if parent_tag_name.is_special_tag() {
-            if let Some(within_special_tag) = &mut node.within_special_tag {
-                within_special_tag.push(parent_tag_name.clone());
-            } else {
-                node.within_special_tag = Some(vec![parent_tag_name.clone()]);
+/// See <https://html.spec.whatwg.org/multipage/parsing.html#doctype-state>
+fn parse_doctype(content: &str) -> Doctype {
+    let mut doctype = Doctype::default();
+
+    let rest = content.trim_start();
+    let name_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    if name_end == 0 {
+        // a DOCTYPE without a name forces quirks mode
+        doctype.force_quirks = true;
+        return doctype;
+    }
+    doctype.name = Some(rest[..name_end].to_ascii_lowercase());
+
+    let rest = rest[name_end..].trim_start();
+    if rest.is_empty() {
+        return doctype;
+    }
+    let keyword = rest.get(..6).unwrap_or_default();
+    let is_public = keyword.eq_ignore_ascii_case("PUBLIC");
+    if !is_public && !keyword.eq_ignore_ascii_case("SYSTEM") {
+        // anything else than an identifier is a bogus DOCTYPE
+        doctype.force_quirks = true;
+        return doctype;
+    }
+
+    let mut rest = &rest[6..];
+    let mut identifiers = Vec::new();
+    // a public identifier can be followed by a system identifier
+    for _ in 0..if is_public { 2 } else { 1 } {
+        rest = rest.trim_start();
+        let Some(quotation_mark) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            break;
+        };
+        match rest[1..].find(quotation_mark) {
+            Some(end) => {
+                identifiers.push(rest[1..end + 1].to_string());
+                rest = &rest[end + 2..];
+            }
+            None => {
+                // an identifier cut off by the closing bracket forces quirks mode
+                identifiers.push(rest[1..].to_string());
+                doctype.force_quirks = true;
+                rest = "";
             }
         }
     }
+
+    if identifiers.is_empty() || (is_public && identifiers.len() == 1 && !rest.trim().is_empty()) {
+        // a missing identifier, or anything else than a system identifier after a public identifier, forces quirks mode
+        doctype.force_quirks = true;
+    }
+    let mut identifiers = identifiers.into_iter();
+    if is_public {
+        doctype.public_id = identifiers.next();
+    }
+    doctype.system_id = identifiers.next();
+
+    doctype
+}
+
+/// The markup declarations, other than DOCTYPE, that start with `<!` or `<?`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MarkupDeclaration<'a> {
+    /// a comment (i.e. `<!-- comment -->`), or a bogus comment (i.e. `<!ELEMENT br EMPTY>`) with its content
+    Comment(&'a str),
+    /// a CDATA section (i.e. `<![CDATA[x < y]]>`) with its content, which is a text
+    CData(&'a str),
+    /// a processing instruction (i.e. `<?xml version="1.0"?>`)
+    ProcessingInstruction,
+}
+
+/// Parses the markup declaration at the start of `rest`
+///
+/// Returns the declaration and its length, or None if the end of the declaration is missing.
+/// The search for the end continues from `resume` when the declaration was cut off by the end of the last chunk.
+fn parse_markup_declaration(rest: &str, resume: Resume) -> Option<(MarkupDeclaration<'_>, usize)> {
+    if let Some(comment) = rest.strip_prefix("<!--") {
+        // <!--> and <!---> are empty comments
+        if comment.starts_with('>') {
+            return Some((MarkupDeclaration::Comment(""), 5));
+        }
+        if comment.starts_with("->") {
+            return Some((MarkupDeclaration::Comment(""), 6));
+        }
+        // a comment ends with --> or --!>
+        let from = resume.index(comment, 3 + 4);
+        let (content_end, closing_length) =
+            comment[from..].match_indices('-').find_map(|(index, _)| {
+                let index = from + index;
+                let after = &comment[index..];
+                if after.starts_with("-->") {
+                    Some((index, 3))
+                } else if after.starts_with("--!>") {
+                    Some((index, 4))
+                } else {
+                    None
+                }
+            })?;
+        return Some((
+            MarkupDeclaration::Comment(&comment[..content_end]),
+            4 + content_end + closing_length,
+        ));
+    }
+    if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+        let from = resume.index(cdata, 2 + 9);
+        let end = cdata[from..].find("]]>")? + from;
+        return Some((MarkupDeclaration::CData(&cdata[..end]), 9 + end + 3));
+    }
+    // anything else ends at the first closing bracket
+    let from = resume.index(rest, 0);
+    let end = rest[from..].find('>')? + from;
+    if rest.starts_with("<?") {
+        return Some((MarkupDeclaration::ProcessingInstruction, end + 1));
+    }
+    Some((MarkupDeclaration::Comment(&rest[2..end]), end + 1))
+}
+
+/// Reads the markup declaration at the start of `rest` whose end is missing up to the end of the input, the way browsers do
+fn unterminated_markup_declaration(rest: &str) -> MarkupDeclaration<'_> {
+    if let Some(comment) = rest.strip_prefix("<!--") {
+        return MarkupDeclaration::Comment(comment);
+    }
+    if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+        return MarkupDeclaration::CData(cdata);
+    }
+    if rest.starts_with("<?") {
+        return MarkupDeclaration::ProcessingInstruction;
+    }
+    MarkupDeclaration::Comment(&rest[2..])
+}
+
+/// Checks whether the less-than sign at the start of `rest` is followed by a tag name (i.e. `<p`, `</p`),
+/// which is required for browsers to read it as a tag
+fn starts_tag_name(rest: &str) -> bool {
+    let name = rest[1..].strip_prefix('/').unwrap_or(&rest[1..]);
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+}
+
+/// The ways the content of an element is read other than as markup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RawTextKind {
+    /// the content is read as it is up to the closing tag (i.e. `<script>`, `<style>`)
+    Raw,
+    /// the content is read up to the closing tag, decoding character references (i.e. `<textarea>`, `<title>`)
+    Escapable,
+    /// the rest of the input is read as it is (i.e. `<plaintext>`)
+    Plaintext,
+}
+
+impl RawTextKind {
+    fn from_node_type(node_type: &NodeType) -> Option<Self> {
+        match node_type {
+            NodeType::Script | NodeType::Style => Some(RawTextKind::Raw),
+            NodeType::Title => Some(RawTextKind::Escapable),
+            NodeType::Unknown(tag) => match tag.as_str() {
+                "iframe" | "noembed" | "noframes" | "xmp" => Some(RawTextKind::Raw),
+                "textarea" => Some(RawTextKind::Escapable),
+                "plaintext" => Some(RawTextKind::Plaintext),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Finds the index of the closing tag of a raw text element (i.e. `</script>`) in `rest`
+///
+/// The tag name is matched case-insensitively, and must be followed by a whitespace, a forward slash or a closing bracket.
+/// Returns None if the closing tag is not found.
+/// The first `scanned` bytes, which were searched with the last chunk, are skipped except for a closing tag cut off at their end.
+fn find_raw_text_end(rest: &str, tag_name: &str, scanned: usize) -> Option<usize> {
+    // the closing tag is `</`, the tag name and a character of up to 4 bytes
+    let from = floor_char_boundary(
+        rest,
+        scanned.saturating_sub(tag_name.len() + 5).min(rest.len()),
+    );
+    rest[from..]
+        .match_indices("</")
+        .map(|(index, _)| from + index)
+        .find(|&index| {
+            let name_start = index + 2;
+            let name_end = name_start + tag_name.len();
+            rest.get(name_start..name_end)
+                .is_some_and(|name| name.eq_ignore_ascii_case(tag_name))
+                && rest[name_end..]
+                    .chars()
+                    .next()
+                    .is_some_and(|next| next.is_whitespace() || next == '/' || next == '>')
+        })
 }
 
 /// Parses a string of HTML into a Node struct
@@ -408,7 +1513,9 @@ fn modify_node_with_parent(node: &mut Node, parent: &Node) {
 ///         attributes: None,
 ///         within_special_tag: None,
 ///         children: Vec::new(),
+///         span: None,
 ///     }],
+///     span: None,
 /// };
 ///
 /// assert_eq!(parsed, expected);
@@ -425,164 +1532,305 @@ pub fn parse_html(input: String) -> Node {
     }
 }
 
-fn parse_tag_attributes(
-    tag_attributes: &str,
-    current_index: usize,
-) -> Result<Option<Attributes>, ParseHTMLError> {
-    let tag_attributes = tag_attributes.trim();
+/// The states of the attribute tokenizer, following the attribute states of the HTML5 tokenizer
+///
+/// See <https://html.spec.whatwg.org/multipage/parsing.html#before-attribute-name-state>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttributeState {
+    BeforeName,
+    Name,
+    AfterName,
+    BeforeValue,
+    DoubleQuotedValue,
+    SingleQuotedValue,
+    UnquotedValue,
+    AfterQuotedValue,
+}
 
-    // if the input is empty or only whitespace, return None
-    if tag_attributes.is_empty() {
-        return Ok(None);
-    }
+impl<V: TextValue> ParseState<V> {
+    /// Parses the attributes of a tag (i.e. ` id="main" hidden` in `<div id="main" hidden>`)
+    ///
+    /// `offset` is the byte offset of the attributes in the input, which is used to report the position of errors.
+    /// The errors are reported, so that the lenient mode can recover from them.
+    /// The values are coerced to the types of the attributes of the element (i.e. `colspan` of `<td>` to a number).
+    /// Returns the attributes along with whether the tag is self-closing, which is when it ends with a forward slash
+    /// outside of the attribute values (i.e. `<br/>`, but not `<a href=/>`).
+    fn parse_tag_attributes(
+        &mut self,
+        node_type: &NodeType,
+        tag_attributes: &str,
+        input: &str,
+        offset: usize,
+    ) -> Result<(Option<Attributes>, bool), ParseHTMLError> {
+        use AttributeState::*;
 
-    let mut attribute_map = Attributes::new();
+        // if the input is empty or only whitespace, return None
+        if tag_attributes.trim().is_empty() {
+            return Ok((None, false));
+        }
 
-    let mut current_key = String::new();
-    let mut current_value_in_quotes = String::new();
-    let mut in_quotes = false;
-    let mut may_be_reading_non_quoted_value = false;
+        let mut attribute_map = Attributes::new();
 
-    for char in tag_attributes.trim().chars() {
-        // iterate through each character in the trimmed tag_attributes string
+        let mut state = BeforeName;
+        let mut current_key = String::new();
+        let mut current_value = String::new();
+        // whether the current attribute has a value (i.e. key="value"), as opposed to a boolean attribute (i.e. key)
+        let mut has_value = false;
 
-        if in_quotes {
-            // if we are in quotation marks, just add the character to the current_value_in_quotes
-            // except for if the character is a quotation mark, which indicates the end of the value
-            if char.eq(&'"') {
-                // if the character is a quotation mark, add the current_value_in_quotes to the attribute_map
-                // and reset the current_key and current_value_in_quotes
-                add_to_attribute_map(&mut attribute_map, &current_key, &current_value_in_quotes);
-                current_key.clear();
-                current_value_in_quotes.clear();
-                in_quotes = false;
-                continue;
-            }
-            current_value_in_quotes.push(char);
-            continue;
-        }
-
-        if char.eq(&'"') {
-            // if the character is a quotation mark, we are about to start the value
-            // we know in_quotes is false because that is checked above
-            if current_key.is_empty() {
-                // if the current_key is empty, the attribute is malformed
-                return Err(ParseHTMLError::MalformedAttribute(
-                    tag_attributes.to_string(),
-                    MalformedAttributeError::MissingAttributeName(current_index as u32),
-                ));
-            }
-            // set the in_quotes flag to true
-            in_quotes = true;
-            // if the character is a quotation mark, we are going to be in quotes
-            // so we don't need to keep track of non-quoted value flag
-            may_be_reading_non_quoted_value = false;
-            continue;
-        }
-
-        if char.is_whitespace() {
-            if may_be_reading_non_quoted_value {
-                if current_value_in_quotes.is_empty() {
-                    // if we are reading a non-quoted value and the value is empty, we can ignore the whitespace
-                    continue;
+        // value_start is the index of the quotation mark starting the current value
+        let mut value_start = 0;
+        // name_start is the byte offset of the current attribute name in the input
+        let mut name_start = offset;
+        // whether the last character is a forward slash between attributes, which makes the tag self-closing
+        let mut self_closing = false;
+
+        for (index, char) in tag_attributes.char_indices() {
+            self_closing =
+                char == '/' && matches!(state, BeforeName | AfterQuotedValue | Name | AfterName);
+            match state {
+                BeforeName | AfterQuotedValue => {
+                    // whitespace and stray forward slashes between attributes are ignored
+                    if char.is_whitespace() || char == '/' {
+                        state = BeforeName;
+                        continue;
+                    }
+                    if char == '=' {
+                        // an equal sign cannot start an attribute name
+                        // browsers read it as a part of the name when recovering (i.e. `<img =a>` has an attribute named `=a`)
+                        let error = self.malformed_attribute(
+                            input,
+                            offset + index,
+                            MalformedAttributeError::MissingAttributeName,
+                        );
+                        self.report(error)?;
+                    }
+                    // otherwise, the character starts a new attribute name
+                    name_start = offset + index;
+                    current_key.push(char.to_ascii_lowercase());
+                    state = Name;
+                }
+                Name | AfterName => match char {
+                    char if char.is_whitespace() => state = AfterName,
+                    '=' => {
+                        has_value = true;
+                        state = BeforeValue;
+                    }
+                    '/' => {
+                        // a forward slash ends the attribute name, the attribute has no value
+                        self.add_attribute(
+                            &mut attribute_map,
+                            &mut current_key,
+                            None,
+                            node_type,
+                            input,
+                            name_start,
+                        )?;
+                        state = BeforeName;
+                    }
+                    char => {
+                        if state == AfterName {
+                            // the previous attribute has no value, and the character starts a new attribute name
+                            self.add_attribute(
+                                &mut attribute_map,
+                                &mut current_key,
+                                None,
+                                node_type,
+                                input,
+                                name_start,
+                            )?;
+                        }
+                        if current_key.is_empty() {
+                            name_start = offset + index;
+                        }
+                        current_key.push(char.to_ascii_lowercase());
+                        state = Name;
+                    }
+                },
+                BeforeValue => match char {
+                    char if char.is_whitespace() => continue,
+                    '"' => {
+                        value_start = index;
+                        state = DoubleQuotedValue;
+                    }
+                    '\'' => {
+                        value_start = index;
+                        state = SingleQuotedValue;
+                    }
+                    char => {
+                        current_value.push(char);
+                        state = UnquotedValue;
+                    }
+                },
+                DoubleQuotedValue | SingleQuotedValue => {
+                    let closing_quotation_mark = if state == DoubleQuotedValue {
+                        '"'
+                    } else {
+                        '\''
+                    };
+                    if char == closing_quotation_mark {
+                        self.add_attribute(
+                            &mut attribute_map,
+                            &mut current_key,
+                            Some(&mut current_value),
+                            node_type,
+                            input,
+                            name_start,
+                        )?;
+                        has_value = false;
+                        state = AfterQuotedValue;
+                        continue;
+                    }
+                    current_value.push(char);
+                }
+                UnquotedValue => {
+                    // an unquoted value ends at the next whitespace
+                    if char.is_whitespace() {
+                        self.add_attribute(
+                            &mut attribute_map,
+                            &mut current_key,
+                            Some(&mut current_value),
+                            node_type,
+                            input,
+                            name_start,
+                        )?;
+                        has_value = false;
+                        state = BeforeName;
+                        continue;
+                    }
+                    current_value.push(char);
                 }
-                // if we are reading a non-quoted value, the whitespace indicates the end of the value
-                // add the value to the attribute_map
-                add_to_attribute_map(&mut attribute_map, &current_key, &current_value_in_quotes);
-                current_key.clear();
-                current_value_in_quotes.clear();
-                may_be_reading_non_quoted_value = false;
-                continue;
             }
-            // if the character is whitespace, if could be indicating the end of a key
-            if !current_key.is_empty() {
-                // if the key has some value, add it to the attribute_map with value true
-                attribute_map.insert(current_key.clone(), AttributeValues::from(true));
-                current_key.clear();
-                continue;
+        }
+
+        match state {
+            DoubleQuotedValue | SingleQuotedValue => {
+                let error = self.malformed_attribute(
+                    input,
+                    offset + value_start,
+                    MalformedAttributeError::MissingQuotationMark,
+                );
+                self.report(error)?;
+                // the value is read up to the end of the tag when recovering
+                self.add_attribute(
+                    &mut attribute_map,
+                    &mut current_key,
+                    Some(&mut current_value),
+                    node_type,
+                    input,
+                    name_start,
+                )?;
+            }
+            BeforeValue => {
+                // the tag ends right after the equal sign (i.e. `<a href=>`), and the value is empty when recovering
+                let error = self.malformed_attribute(
+                    input,
+                    offset + tag_attributes.len(),
+                    MalformedAttributeError::MissingAttributeValue,
+                );
+                self.report(error)?;
+                self.add_attribute(
+                    &mut attribute_map,
+                    &mut current_key,
+                    Some(&mut current_value),
+                    node_type,
+                    input,
+                    name_start,
+                )?;
             }
-            // if the current_key is empty, the whitespace can be ignored
-            continue;
-        }
-
-        if !in_quotes && !may_be_reading_non_quoted_value && char.eq(&'=') {
-            // if the character is an equal sign, the current_key is complete
-            // if we are in quotes or reading a non-quoted value, the equal sign is part of the value
-            // and we are about to start the value
-            if current_key.is_empty() {
-                // if the current_key is empty, the attribute is malformed
-                return Err(ParseHTMLError::MalformedAttribute(
-                    tag_attributes.to_string(),
-                    MalformedAttributeError::MissingAttributeName(current_index as u32),
-                ));
+            Name | AfterName | UnquotedValue => {
+                let value = has_value.then_some(&mut current_value);
+                self.add_attribute(
+                    &mut attribute_map,
+                    &mut current_key,
+                    value,
+                    node_type,
+                    input,
+                    name_start,
+                )?;
             }
-            // equal sign indicates the start of the value up to the next whitespace
-            may_be_reading_non_quoted_value = true;
-            continue;
+            BeforeName | AfterQuotedValue => (),
         }
 
-        if may_be_reading_non_quoted_value {
-            // if we are reading a non-quoted value, add the character to the current_value_in_quotes
-            current_value_in_quotes.push(char);
-            continue;
+        // if not, return the attribute map
+        match attribute_map.is_empty() {
+            true => Ok((None, self_closing)),
+            false => Ok((Some(attribute_map), self_closing)),
         }
-
-        // otherwise, add the character to the current_key
-        current_key.push(char);
-    }
-
-    if may_be_reading_non_quoted_value && !current_value_in_quotes.is_empty() {
-        // if we are reading a non-quoted value and the value is not empty, add the value to the attribute_map
-        add_to_attribute_map(&mut attribute_map, &current_key, &current_value_in_quotes);
     }
+}
 
-    if in_quotes {
-        return Err(ParseHTMLError::MalformedAttribute(
-            current_value_in_quotes,
-            MalformedAttributeError::MissingQuotationMark(current_index as u32),
-        ));
+/// Converts the value of an attribute to the type of the attribute of the element
+///
+/// Numeric attributes (i.e. `<ol start="3">`) become numbers unless the value is not a valid integer,
+/// and boolean attributes (i.e. `<input checked="checked">`) become `true` whatever their value is.
+fn coerce_attribute_value(node_type: &NodeType, key: &str, value: String) -> AttributeValues {
+    let is_number = match key {
+        "start" | "colspan" | "rowspan" | "width" => true,
+        // the value of other elements (i.e. `<input>`) is a text
+        "value" => node_type == &NodeType::Li,
+        _ => false,
+    };
+    if is_number {
+        return match value.trim().parse::<i32>() {
+            Ok(number) => AttributeValues::from(number),
+            Err(_) => AttributeValues::from(value),
+        };
     }
-
-    // if not, return the attribute map
-    match attribute_map.is_empty() {
-        true => Ok(None),
-        false => Ok(Some(attribute_map)),
+    match key {
+        // `hidden="until-found"` is a state rather than a boolean
+        "hidden" if value.eq_ignore_ascii_case("until-found") => AttributeValues::from(value),
+        "checked" | "hidden" | "open" => AttributeValues::from(true),
+        _ => AttributeValues::from(value),
     }
 }
 
-fn add_to_attribute_map(
-    attribute_map: &mut Attributes,
-    current_key: &str,
-    current_value_in_quotes: &str,
-) {
-    if current_key.is_empty() || current_value_in_quotes.is_empty() {
-        return;
-    }
-    attribute_map.insert(
-        current_key.to_string(),
-        AttributeValues::from(current_value_in_quotes),
-    );
+/// The states used to find the closing bracket of a tag, which follow the states of `parse_tag_attributes`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BracketState {
+    TagName,
+    BeforeName,
+    Name,
+    AfterName,
+    BeforeValue,
+    QuotedValue(char),
+    UnquotedValue,
 }
 
-fn find_closing_bracket_index(rest: &str) -> Option<usize> {
-    let mut attribute_value_stack: VecDeque<char> = VecDeque::new(); // needed to fix #31
-    for (idx, char) in rest.char_indices() {
-        if char.eq(&'"') || char.eq(&'\'') {
-            if let Some(back) = attribute_value_stack.back() {
-                if back.eq(&char) {
-                    attribute_value_stack.pop_back();
-                } else {
-                    attribute_value_stack.push_back(char)
-                }
-            } else {
-                attribute_value_stack.push_back(char)
-            }
-        }
-        if char.eq(&'>') && attribute_value_stack.is_empty() {
-            return Some(idx);
-        }
+/// Finds the index of the closing bracket of the tag at the start of `rest`
+///
+/// Closing brackets inside quoted attribute values (i.e. `alt="a>b"`) are skipped.
+/// Quotation marks only start a value right after the equal sign following an attribute name, like in the HTML5 tokenizer,
+/// so that they are a part of the name in a tag name (i.e. `<a"b>`) or an attribute name (i.e. `<p =">`).
+/// The search starts at the index and the state where it stopped with the last chunk (i.e. `(1, TagName)` for a new tag),
+/// and returns the state at the end of `rest` if the closing bracket is not found.
+fn find_closing_bracket_index(
+    rest: &str,
+    (from, mut state): (usize, BracketState),
+) -> Result<usize, BracketState> {
+    use BracketState::*;
+
+    // the state is needed to fix #31
+    for (idx, char) in rest[from..].char_indices() {
+        let idx = from + idx;
+        state = match (state, char) {
+            (QuotedValue(quotation_mark), char) if char == quotation_mark => BeforeName,
+            (QuotedValue(quotation_mark), _) => QuotedValue(quotation_mark),
+            (_, '>') => return Ok(idx),
+            (TagName | BeforeName | Name | AfterName, '/') => BeforeName,
+            (TagName, char) if char.is_whitespace() => BeforeName,
+            (TagName, _) => TagName,
+            (BeforeName, char) if char.is_whitespace() => BeforeName,
+            (Name | AfterName, char) if char.is_whitespace() => AfterName,
+            (Name | AfterName, '=') => BeforeValue,
+            // an equal sign starts an attribute name, which is a malformed attribute
+            (BeforeName | Name | AfterName, _) => Name,
+            (BeforeValue, '"' | '\'') => QuotedValue(char),
+            (BeforeValue, char) if char.is_whitespace() => BeforeValue,
+            (UnquotedValue, char) if char.is_whitespace() => BeforeName,
+            (BeforeValue | UnquotedValue, _) => UnquotedValue,
+        };
     }
-    None
+    Err(state)
 }
 
 // https://github.com/izyuumi/html2md-rs/issues/25
@@ -593,7 +1841,11 @@ fn issue_25() {
         ("property".to_string(), AttributeValues::from("og:type")),
         ("content".to_string(), AttributeValues::from("website")),
     ]);
-    let parsed = parse_tag_attributes(&input, 0).unwrap().unwrap();
+    let parsed = ParseState::<String>::new(ParseConfig::default())
+        .parse_tag_attributes(&NodeType::Meta, &input, &input, 0)
+        .unwrap()
+        .0
+        .unwrap();
     assert_eq!(parsed, expected);
 }
 
@@ -602,7 +1854,7 @@ fn issue_25() {
 fn issue_31() {
     let input = r#"<img src="https://exmaple.com/img.png" alt="Rust<br/>Logo"/>"#.to_string();
     let expected = Node {
-        tag_name: Some(Unknown("img".to_string())),
+        tag_name: Some(NodeType::Unknown("img".to_string())),
         value: None,
         attributes: Some(Attributes {
             id: None,
@@ -618,6 +1870,7 @@ fn issue_31() {
         }),
         children: Vec::new(),
         within_special_tag: None,
+        span: None,
     };
     let parsed = safe_parse_html(input).unwrap();
     assert_eq!(parsed, expected)
@@ -628,7 +1881,7 @@ fn issue_31() {
 fn issue_36() {
     let input = "<img src=\"https://hoerspiele.dra.de/fileadmin/www.hoerspiele.dra.de/images/vollinfo/4970918_B01.jpg\" />".to_string();
     let expected = Node {
-        tag_name: Some(Unknown("img".to_string())),
+        tag_name: Some(NodeType::Unknown("img".to_string())),
         value: None,
         attributes: Some(Attributes {
             id: None,
@@ -641,6 +1894,7 @@ fn issue_36() {
         }),
         children: Vec::new(),
         within_special_tag: None,
+        span: None,
     };
     let parsed = safe_parse_html(input).unwrap();
     assert_eq!(parsed, expected);
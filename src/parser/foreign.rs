@@ -0,0 +1,225 @@
+//! This module contains the rules for the foreign elements inside `<svg>` and `<math>`.
+//!
+//! Unlike HTML, the names of SVG elements and attributes are case-sensitive (i.e. `viewBox`), so the lowercase names
+//! read by the parser are adjusted back following the tables of the HTML5 tree construction.
+//! See <https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inforeign>
+
+use crate::structs::{AttributeValues, Attributes, GenericNode, Namespace, NodeType};
+
+/// The SVG element names whose case is adjusted, sorted by their lowercase names
+const SVG_ELEMENT_NAMES: [&str; 37] = [
+    "altGlyph",
+    "altGlyphDef",
+    "altGlyphItem",
+    "animateColor",
+    "animateMotion",
+    "animateTransform",
+    "clipPath",
+    "feBlend",
+    "feColorMatrix",
+    "feComponentTransfer",
+    "feComposite",
+    "feConvolveMatrix",
+    "feDiffuseLighting",
+    "feDisplacementMap",
+    "feDistantLight",
+    "feDropShadow",
+    "feFlood",
+    "feFuncA",
+    "feFuncB",
+    "feFuncG",
+    "feFuncR",
+    "feGaussianBlur",
+    "feImage",
+    "feMerge",
+    "feMergeNode",
+    "feMorphology",
+    "feOffset",
+    "fePointLight",
+    "feSpecularLighting",
+    "feSpotLight",
+    "feTile",
+    "feTurbulence",
+    "foreignObject",
+    "glyphRef",
+    "linearGradient",
+    "radialGradient",
+    "textPath",
+];
+
+/// The SVG attribute names whose case is adjusted, sorted by their lowercase names
+const SVG_ATTRIBUTE_NAMES: [&str; 58] = [
+    "attributeName",
+    "attributeType",
+    "baseFrequency",
+    "baseProfile",
+    "calcMode",
+    "clipPathUnits",
+    "diffuseConstant",
+    "edgeMode",
+    "filterUnits",
+    "glyphRef",
+    "gradientTransform",
+    "gradientUnits",
+    "kernelMatrix",
+    "kernelUnitLength",
+    "keyPoints",
+    "keySplines",
+    "keyTimes",
+    "lengthAdjust",
+    "limitingConeAngle",
+    "markerHeight",
+    "markerUnits",
+    "markerWidth",
+    "maskContentUnits",
+    "maskUnits",
+    "numOctaves",
+    "pathLength",
+    "patternContentUnits",
+    "patternTransform",
+    "patternUnits",
+    "pointsAtX",
+    "pointsAtY",
+    "pointsAtZ",
+    "preserveAlpha",
+    "preserveAspectRatio",
+    "primitiveUnits",
+    "refX",
+    "refY",
+    "repeatCount",
+    "repeatDur",
+    "requiredExtensions",
+    "requiredFeatures",
+    "specularConstant",
+    "specularExponent",
+    "spreadMethod",
+    "startOffset",
+    "stdDeviation",
+    "stitchTiles",
+    "surfaceScale",
+    "systemLanguage",
+    "tableValues",
+    "targetX",
+    "targetY",
+    "textLength",
+    "viewBox",
+    "viewTarget",
+    "xChannelSelector",
+    "yChannelSelector",
+    "zoomAndPan",
+];
+
+/// Returns the name with its case adjusted if it is in the table, which is sorted by the lowercase names
+fn adjust_case(table: &[&'static str], name: &str) -> Option<&'static str> {
+    table
+        .binary_search_by(|adjusted| {
+            adjusted
+                .bytes()
+                .map(|byte| byte.to_ascii_lowercase())
+                .cmp(name.bytes())
+        })
+        .ok()
+        .map(|index| table[index])
+}
+
+/// Returns the foreign element for the tag read by the parser in the namespace
+pub(crate) fn foreign_node_type(namespace: Namespace, node_type: &NodeType) -> NodeType {
+    let name = node_type.tag_str();
+    let name = match namespace {
+        Namespace::Svg => adjust_case(&SVG_ELEMENT_NAMES, name).unwrap_or(name),
+        _ => name,
+    };
+    NodeType::Foreign(namespace, name.to_string())
+}
+
+/// Adjusts the case of the attribute names of a foreign element (i.e. `viewbox` to `viewBox`)
+pub(crate) fn adjust_attributes(namespace: Namespace, attributes: &mut Attributes) {
+    let adjusted = attributes
+        .attributes
+        .keys()
+        .filter_map(|name| {
+            let adjusted = match namespace {
+                Namespace::Svg => adjust_case(&SVG_ATTRIBUTE_NAMES, name)?,
+                Namespace::MathMl if name == "definitionurl" => "definitionURL",
+                _ => return None,
+            };
+            Some((name.clone(), adjusted))
+        })
+        .collect::<Vec<_>>();
+    for (name, adjusted) in adjusted {
+        if let Some(value) = attributes.attributes.remove(&name) {
+            attributes.attributes.insert(adjusted.to_string(), value);
+        }
+    }
+}
+
+/// Checks whether the HTML start tag breaks out of foreign content (i.e. `<p>` in `<svg>`),
+/// closing the foreign elements up to the nearest HTML element or integration point
+pub(crate) fn is_breakout(node_type: &NodeType, attributes: Option<&Attributes>) -> bool {
+    use NodeType::*;
+    match node_type {
+        Body | Br | Code | Div | Em | H1 | H2 | H3 | H4 | H5 | H6 | Head | Hr | Li | Meta | Ol
+        | P | Pre | Strong | Ul | Blockquote => true,
+        Unknown(tag) if tag == "font" => attributes.is_some_and(|attributes| {
+            ["color", "face", "size"]
+                .iter()
+                .any(|name| attributes.contains(name))
+        }),
+        Unknown(tag) => matches!(
+            tag.as_str(),
+            "b" | "big"
+                | "center"
+                | "dd"
+                | "dl"
+                | "dt"
+                | "embed"
+                | "i"
+                | "img"
+                | "listing"
+                | "menu"
+                | "nobr"
+                | "ruby"
+                | "s"
+                | "small"
+                | "span"
+                | "strike"
+                | "sub"
+                | "sup"
+                | "table"
+                | "tt"
+                | "u"
+                | "var"
+        ),
+        _ => false,
+    }
+}
+
+/// Checks whether the foreign element contains HTML (i.e. `<foreignObject>` in `<svg>`)
+pub(crate) fn is_html_integration_point<V>(node: &GenericNode<V>) -> bool {
+    match &node.tag_name {
+        Some(NodeType::Foreign(Namespace::Svg, name)) => {
+            matches!(name.as_str(), "foreignObject" | "desc" | "title")
+        }
+        Some(NodeType::Foreign(Namespace::MathMl, name)) if name == "annotation-xml" => node
+            .attributes
+            .as_ref()
+            .and_then(|attributes| attributes.get("encoding"))
+            .is_some_and(|encoding| match encoding {
+                AttributeValues::String(encoding) => {
+                    encoding.eq_ignore_ascii_case("text/html")
+                        || encoding.eq_ignore_ascii_case("application/xhtml+xml")
+                }
+                _ => false,
+            }),
+        _ => false,
+    }
+}
+
+/// Checks whether the MathML element contains HTML elements and texts (i.e. `<mi>`), except for `<mglyph>` and `<malignmark>`
+pub(crate) fn is_mathml_text_integration_point(node_type: &NodeType) -> bool {
+    matches!(
+        node_type,
+        NodeType::Foreign(Namespace::MathMl, name)
+            if matches!(name.as_str(), "mi" | "mo" | "mn" | "ms" | "mtext")
+    )
+}
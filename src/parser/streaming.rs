@@ -0,0 +1,173 @@
+//! This module contains the StreamingParser struct, which parses HTML incrementally from chunks of bytes or a reader.
+//!
+//! Tags, comments, texts and character references split across chunks are kept until they are complete,
+//! so the result is the same as parsing the whole input at once with `safe_parse_html`.
+
+use super::{ParseHTMLError, ParseState};
+use crate::structs::{Node, ParseConfig, ParsedHtml};
+use std::io::{self, Read};
+
+/// The size of the buffer used to read from a reader
+const READ_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Parses HTML incrementally from chunks of UTF-8 bytes
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::parser::{safe_parse_html, StreamingParser};
+///
+/// let mut parser = StreamingParser::new();
+/// parser.feed(b"<p>Tom &am").unwrap();
+/// parser.feed(b"p; Jerry</").unwrap();
+/// parser.feed(b"p>").unwrap();
+/// let parsed = parser.finish().unwrap();
+///
+/// let expected = safe_parse_html("<p>Tom &amp; Jerry</p>".to_string()).unwrap();
+/// assert_eq!(parsed.root, expected);
+/// ```
+#[derive(Debug)]
+pub struct StreamingParser {
+    state: ParseState<String>,
+    /// buffer holds the input which is not parsed yet, such as a tag without its closing bracket
+    buffer: String,
+    /// incomplete holds the bytes at the end of the last chunk which start a multi-byte character
+    incomplete: Vec<u8>,
+}
+
+impl Default for StreamingParser {
+    fn default() -> Self {
+        StreamingParser::new()
+    }
+}
+
+impl StreamingParser {
+    /// Creates a new StreamingParser with the default configuration
+    pub fn new() -> Self {
+        StreamingParser::with_config(ParseConfig::default())
+    }
+
+    /// Creates a new StreamingParser with a custom configuration
+    pub fn with_config(config: ParseConfig) -> Self {
+        StreamingParser {
+            state: ParseState::new(config),
+            buffer: String::new(),
+            incomplete: Vec::new(),
+        }
+    }
+
+    /// Parses a chunk of UTF-8 bytes, which continues from the previous chunk
+    ///
+    /// A multi-byte character can be split across chunks, while invalid bytes are replaced with U+FFFD.
+    /// Once an error is returned, the parser should not be fed anymore.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), ParseHTMLError> {
+        self.decode(chunk);
+        self.parse_buffer()
+    }
+
+    /// Parses a chunk of string, which continues from the previous chunk
+    ///
+    /// Once an error is returned, the parser should not be fed anymore.
+    pub fn feed_str(&mut self, chunk: &str) -> Result<(), ParseHTMLError> {
+        self.flush_incomplete();
+        self.buffer.push_str(chunk);
+        self.parse_buffer()
+    }
+
+    /// Reads and parses the reader until its end
+    ///
+    /// Parse errors are returned as `io::ErrorKind::InvalidData` errors wrapping the ParseHTMLError.
+    pub fn feed_reader(&mut self, mut reader: impl Read) -> io::Result<()> {
+        let mut chunk = vec![0; READ_BUFFER_SIZE];
+        loop {
+            let read = match reader.read(&mut chunk) {
+                Ok(0) => return Ok(()),
+                Ok(read) => read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            self.feed(&chunk[..read])
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        }
+    }
+
+    /// Parses the rest of the input as the end of the document, and returns the result
+    pub fn finish(mut self) -> Result<ParsedHtml, ParseHTMLError> {
+        self.flush_incomplete();
+        let parsed = self.state.parse(&self.buffer, true)?;
+        Ok(self.state.finish(&self.buffer[parsed..]))
+    }
+
+    /// Parses the complete tags and texts in the buffer, keeping the rest for the next chunk
+    fn parse_buffer(&mut self) -> Result<(), ParseHTMLError> {
+        let parsed = self.state.parse(&self.buffer, false)?;
+        self.buffer.drain(..parsed);
+        Ok(())
+    }
+
+    /// Decodes the chunk into the buffer, keeping an incomplete character at its end for the next chunk
+    fn decode(&mut self, chunk: &[u8]) {
+        let mut bytes = std::mem::take(&mut self.incomplete);
+        bytes.extend_from_slice(chunk);
+
+        let mut rest = bytes.as_slice();
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    self.buffer.push_str(valid);
+                    return;
+                }
+                Err(err) => {
+                    let (valid, invalid) = rest.split_at(err.valid_up_to());
+                    self.buffer
+                        .push_str(std::str::from_utf8(valid).unwrap_or_default());
+                    match err.error_len() {
+                        Some(len) => {
+                            self.buffer.push(char::REPLACEMENT_CHARACTER);
+                            rest = &invalid[len..];
+                        }
+                        None => {
+                            // the character might be completed by the next chunk
+                            self.incomplete = invalid.to_vec();
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Replaces an incomplete character left from the last chunk with U+FFFD
+    fn flush_incomplete(&mut self) {
+        if !self.incomplete.is_empty() {
+            self.incomplete.clear();
+            self.buffer.push(char::REPLACEMENT_CHARACTER);
+        }
+    }
+}
+
+/// Safely parses HTML from a reader into a Node struct, without reading it into a string first
+///
+/// Parse errors are returned as `io::ErrorKind::InvalidData` errors wrapping the ParseHTMLError.
+///
+/// # Arguments
+///
+/// * `reader` - A reader of the UTF-8 bytes of the HTML to be parsed
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::parser::{safe_parse_html, safe_parse_html_reader};
+///
+/// let input = "<div>hello</div>";
+/// let parsed = safe_parse_html_reader(input.as_bytes()).unwrap();
+/// assert_eq!(parsed, safe_parse_html(input.to_string()).unwrap());
+/// ```
+pub fn safe_parse_html_reader(reader: impl Read) -> io::Result<Node> {
+    let mut parser = StreamingParser::new();
+    parser.feed_reader(reader)?;
+    parser
+        .finish()
+        .map(|parsed| parsed.root)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
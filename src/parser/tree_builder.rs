@@ -0,0 +1,1018 @@
+//! This module contains the TreeBuilder struct, which builds the Node tree from the tags and texts found by the parser.
+//!
+//! Closing tags are matched against the stack of open elements by name, closing tags without a matching open element are ignored,
+//! and mis-nested formatting elements (i.e. `<b><i>x</b>y</i>`) are recovered in a way similar to the
+//! [adoption agency algorithm](https://html.spec.whatwg.org/multipage/parsing.html#adoption-agency-algorithm) of HTML5.
+
+use std::{borrow::Cow, ops::Range};
+
+use super::{
+    foreign::{
+        adjust_attributes, foreign_node_type, is_breakout, is_html_integration_point,
+        is_mathml_text_integration_point,
+    },
+    whitespace::collapse_whitespace,
+};
+
+use crate::structs::{
+    Attributes, GenericNode, Namespace,
+    NodeType::{self, *},
+    Span,
+};
+
+/// The text values of the nodes built by the TreeBuilder, which are Strings for Node and Cows for BorrowedNode
+pub(crate) trait TextValue: AsRef<str> + Default {
+    /// Appends a text to the value
+    fn push_str(&mut self, text: &str);
+    /// Shortens the value to the byte range of it
+    fn slice(&mut self, range: Range<usize>);
+    /// Replaces the value with a new text
+    fn replace(&mut self, text: String);
+}
+
+impl TextValue for String {
+    fn push_str(&mut self, text: &str) {
+        String::push_str(self, text)
+    }
+
+    fn slice(&mut self, range: Range<usize>) {
+        self.truncate(range.end);
+        self.drain(..range.start);
+    }
+
+    fn replace(&mut self, text: String) {
+        *self = text;
+    }
+}
+
+impl TextValue for Cow<'_, str> {
+    fn push_str(&mut self, text: &str) {
+        self.to_mut().push_str(text)
+    }
+
+    fn slice(&mut self, range: Range<usize>) {
+        match self {
+            Cow::Borrowed(text) => *text = &text[range],
+            Cow::Owned(text) => text.slice(range),
+        }
+    }
+
+    fn replace(&mut self, text: String) {
+        *self = Cow::Owned(text);
+    }
+}
+
+/// The maximum number of times the adoption agency algorithm restructures the tree for a single closing tag
+const ADOPTION_AGENCY_OUTER_LOOP_LIMIT: usize = 8;
+/// The maximum number of formatting elements reopened around a furthest block by the adoption agency algorithm
+const ADOPTION_AGENCY_INNER_LOOP_LIMIT: usize = 3;
+/// The maximum number of identical formatting elements waiting to be reopened (the "Noah's Ark" clause of HTML5)
+const NOAHS_ARK_LIMIT: usize = 3;
+
+/// Builds a Node tree from the tags and texts found by the parser
+#[derive(Debug, Default)]
+pub(crate) struct TreeBuilder<V> {
+    /// nodes is a vector of the closed nodes at the top level, which will be the children of the resulting node
+    nodes: Vec<GenericNode<V>>,
+    /// stack is a LIFO stack of the open elements, each of which is added to its parent when it is closed
+    stack: Vec<GenericNode<V>>,
+    /// active_formatting_elements are the formatting elements that were closed implicitly (i.e. the `<b>` in `<p><b>a</p>b`),
+    /// which are reopened before the next inline content is inserted
+    active_formatting_elements: Vec<GenericNode<V>>,
+    /// position is the span of the tag or text being processed, which is only set when the spans are recorded
+    position: Option<Span>,
+    /// context is the element a fragment is parsed in (i.e. the `<ul>` of `<li>a</li>`), which becomes the resulting node
+    context: Option<GenericNode<V>>,
+    /// node_count is the number of nodes inserted, including the reopened formatting elements
+    node_count: usize,
+    /// open_counts are the numbers of the open elements which most of the start tags look for in the stack
+    open_counts: OpenCounts,
+}
+
+impl<V: TextValue> TreeBuilder<V> {
+    /// Creates a new TreeBuilder without any node
+    pub(crate) fn new() -> Self {
+        TreeBuilder::default()
+    }
+
+    /// Creates a new TreeBuilder for a fragment in the context element, which can't be closed by the fragment
+    pub(crate) fn with_context(context: NodeType) -> Self {
+        TreeBuilder {
+            context: Some(GenericNode {
+                tag_name: Some(context),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the number of nodes inserted so far, except for the context element
+    pub(crate) fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    /// Returns the number of open elements nested in each other
+    pub(crate) fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Removes the open elements nested deeper than `max_depth`, moving their children into their parents
+    pub(crate) fn skip_elements_deeper_than(&mut self, max_depth: usize) {
+        while self.stack.len() > max_depth {
+            let Some(node) = self.pop() else {
+                return;
+            };
+            self.node_count -= 1;
+            match self.stack.last_mut() {
+                Some(parent) => parent.children.extend(node.children),
+                None => self.nodes.extend(node.children),
+            }
+        }
+    }
+
+    /// Inserts a text into the current node
+    ///
+    /// If the last child of the current node is a text, the text is appended to it.
+    pub(crate) fn insert_text(&mut self, text: V, span: Option<Span>) {
+        self.position = span;
+        // whitespace between elements doesn't reopen formatting elements, which would be left empty once it is collapsed
+        if !text.as_ref().trim().is_empty() && !self.in_foreign_content() {
+            self.reconstruct_active_formatting_elements();
+        }
+        let siblings = match self.stack.last_mut() {
+            Some(parent) => &mut parent.children,
+            None => &mut self.nodes,
+        };
+        if let Some(GenericNode {
+            tag_name: Some(Text),
+            value: Some(previous_text),
+            span: previous_span,
+            ..
+        }) = siblings.last_mut()
+        {
+            previous_text.push_str(text.as_ref());
+            if let (Some(previous_span), Some(span)) = (previous_span, span) {
+                previous_span.end = span.end;
+            }
+            return;
+        }
+        self.append(GenericNode {
+            tag_name: Some(Text),
+            value: Some(text),
+            span,
+            ..Default::default()
+        });
+    }
+
+    /// Inserts the content of a raw text element (i.e. `<script>`) into the current node as it is
+    pub(crate) fn insert_raw_text(&mut self, text: V, span: Option<Span>) {
+        self.position = span;
+        self.append(GenericNode {
+            tag_name: Some(Text),
+            value: Some(text),
+            span,
+            ..Default::default()
+        });
+    }
+
+    /// Inserts a comment into the current node
+    pub(crate) fn insert_comment(&mut self, comment: V, span: Option<Span>) {
+        self.position = span;
+        self.append(GenericNode {
+            tag_name: Some(Comment),
+            value: Some(comment),
+            span,
+            ..Default::default()
+        });
+    }
+
+    /// Inserts an element into the current node
+    ///
+    /// Unless the element is self-closing or a void element (i.e. `<br>`), it becomes the current node until it is closed.
+    /// The span of the node is expected to be the span of its start tag, which is extended when the element is closed.
+    pub(crate) fn insert_element(&mut self, mut node: GenericNode<V>, self_closing: bool) {
+        self.position = node.span;
+        let node_type = node.tag_name.clone().unwrap_or_default();
+
+        let namespace = self.namespace_for(&node_type, node.attributes.as_ref());
+        if namespace != Namespace::Html {
+            if !self.in_foreign_content() {
+                // the <svg> or <math> element starting foreign content is inserted like an inline element
+                self.reconstruct_active_formatting_elements();
+            }
+            node.tag_name = Some(foreign_node_type(namespace, &node_type));
+            if let Some(attributes) = &mut node.attributes {
+                adjust_attributes(namespace, attributes);
+            }
+            // unlike HTML elements, any foreign element can be self-closing (i.e. `<circle />`)
+            if self_closing {
+                self.append(node);
+            } else {
+                self.push(node);
+            }
+            return;
+        }
+        if self.in_foreign_content() {
+            // an HTML element breaking out of foreign content (i.e. `<p>` in `<svg>`) closes the foreign elements first
+            self.close_foreign_elements();
+        }
+
+        if node_type == A
+            && (self
+                .active_formatting_elements
+                .iter()
+                .any(|n| n.tag_name == Some(A))
+                || (self.open_counts.links > 0
+                    && self
+                        .find_in_scope(Scope::Default, |tag| tag == &A)
+                        .is_some()))
+        {
+            // links cannot be nested, so an open link is closed first
+            self.adoption_agency(&A);
+        }
+
+        self.close_implied_elements(&node_type);
+
+        if !is_special_element(&node_type) {
+            self.reconstruct_active_formatting_elements();
+        }
+
+        if self_closing || node_type.is_void_element() {
+            self.append(node);
+            return;
+        }
+        self.push(node);
+    }
+
+    /// Closes the open element matching the closing tag
+    ///
+    /// Closing tags without a matching open element are ignored.
+    pub(crate) fn close_element(&mut self, node_type: &NodeType, span: Option<Span>) {
+        self.position = span;
+        if self
+            .stack
+            .last()
+            .is_some_and(|current| current.namespace() != Namespace::Html)
+        {
+            if matches!(node_type, Br | P) {
+                // </br> and </p> break out of foreign content like their start tags
+                self.close_foreign_elements();
+            } else if let Some(closed) = self.close_foreign_element(node_type) {
+                if closed {
+                    self.extend_closed_span(span);
+                }
+                return;
+            }
+        }
+        if node_type == &Br {
+            // browsers treat </br> as <br>
+            self.insert_element(
+                GenericNode {
+                    tag_name: Some(Br),
+                    span,
+                    ..Default::default()
+                },
+                true,
+            );
+            return;
+        }
+        if node_type.is_void_element() {
+            // void elements are never open, so their closing tags are ignored
+            return;
+        }
+
+        let closed = match node_type {
+            tag if is_formatting_element(tag) => self.adoption_agency(tag),
+            // any heading closes the innermost open heading (i.e. <h1>...</h2>)
+            h if is_heading(h) => self.close_in_scope(Scope::Default, is_heading),
+            P => self.close_paragraph(),
+            Li => self.close_in_scope(Scope::ListItem, |tag| tag == &Li),
+            tag if is_special_element(tag) => {
+                self.close_in_scope(Scope::Default, |open| open == tag)
+            }
+            _ => self.close_any_other_element(node_type),
+        };
+
+        if closed {
+            self.extend_closed_span(span);
+        }
+    }
+
+    /// Ends the span of the element closed by the closing tag right after the tag, unlike the elements closed implicitly
+    fn extend_closed_span(&mut self, span: Option<Span>) {
+        let Some(span) = span else {
+            return;
+        };
+        let siblings = match self.stack.last_mut() {
+            Some(parent) => &mut parent.children,
+            None => &mut self.nodes,
+        };
+        if let Some(Some(closed_span)) = siblings.last_mut().map(|node| &mut node.span) {
+            closed_span.end = span.end;
+        }
+    }
+
+    /// Returns the namespace of the element starting with the tag, which depends on the current node
+    ///
+    /// `<svg>` and `<math>` start foreign content, whose elements are in the same namespace,
+    /// unless the current node is an integration point containing HTML (i.e. `<foreignObject>`) or the tag breaks out of it.
+    pub(crate) fn namespace_for(
+        &self,
+        node_type: &NodeType,
+        attributes: Option<&Attributes>,
+    ) -> Namespace {
+        if let Some(current) = self.current_node() {
+            let namespace = current.namespace();
+            let is_text_integration_point = current
+                .tag_name
+                .as_ref()
+                .is_some_and(is_mathml_text_integration_point);
+            let is_html_content = is_html_integration_point(current)
+                || (is_text_integration_point
+                    && !matches!(node_type, Unknown(tag) if tag == "mglyph" || tag == "malignmark"));
+            if namespace == Namespace::MathMl
+                && current.tag_name.as_ref().map(NodeType::tag_str) == Some("annotation-xml")
+                && node_type.is_unknown("svg")
+            {
+                return Namespace::Svg;
+            }
+            if namespace != Namespace::Html && !is_html_content {
+                if is_breakout(node_type, attributes) {
+                    return Namespace::Html;
+                }
+                return namespace;
+            }
+        }
+        match node_type {
+            Unknown(tag) if tag == "svg" => Namespace::Svg,
+            Unknown(tag) if tag == "math" => Namespace::MathMl,
+            _ => Namespace::Html,
+        }
+    }
+
+    /// Returns the current node, which is the context element of a fragment if no element is open
+    fn current_node(&self) -> Option<&GenericNode<V>> {
+        self.stack.last().or(self.context.as_ref())
+    }
+
+    /// Checks whether the texts and tags are inserted into foreign content, outside of its integration points
+    fn in_foreign_content(&self) -> bool {
+        self.current_node().is_some_and(|current| {
+            current.namespace() != Namespace::Html
+                && !is_html_integration_point(current)
+                && !current
+                    .tag_name
+                    .as_ref()
+                    .is_some_and(is_mathml_text_integration_point)
+        })
+    }
+
+    /// Closes the foreign elements up to the nearest HTML element or integration point
+    fn close_foreign_elements(&mut self) {
+        while self.in_foreign_content() {
+            let Some(node) = self.pop() else {
+                // the context element of a fragment is never closed
+                return;
+            };
+            self.attach(node);
+        }
+    }
+
+    /// Closes the innermost open foreign element with the same name as the closing tag, compared case-insensitively
+    ///
+    /// Returns None if an HTML element is found first, so that the closing tag is handled as an HTML closing tag.
+    fn close_foreign_element(&mut self, node_type: &NodeType) -> Option<bool> {
+        for index in (0..self.stack.len()).rev() {
+            match self.tag_at(index) {
+                Foreign(_, name) if name.eq_ignore_ascii_case(node_type.tag_str()) => {
+                    self.close_from(index);
+                    return Some(true);
+                }
+                Foreign(..) => continue,
+                _ => return None,
+            }
+        }
+        Some(false)
+    }
+
+    /// Closes the innermost open element with the same name as the closing tag,
+    /// unless a special element (i.e. <div>) is open in between
+    fn close_any_other_element(&mut self, node_type: &NodeType) -> bool {
+        for index in (0..self.stack.len()).rev() {
+            let tag = self.tag_at(index);
+            if tag == node_type {
+                self.close_from(index);
+                return true;
+            }
+            if is_special_element(tag) {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// Closes all of the open elements and returns the resulting node
+    ///
+    /// `span` is the span of the whole input, which is only given when the spans are recorded.
+    pub(crate) fn finish(mut self, span: Option<Span>) -> GenericNode<V> {
+        self.position = span.map(|span| Span {
+            start: span.end,
+            end: span.end,
+        });
+        while let Some(node) = self.pop() {
+            self.attach(node);
+        }
+
+        if let Some(context) = self.context.take() {
+            let mut nodes = vec![GenericNode {
+                children: self.nodes,
+                span,
+                ..context
+            }];
+            collapse_whitespace(&mut nodes);
+            return nodes.remove(0);
+        }
+
+        collapse_whitespace(&mut self.nodes);
+
+        if self.nodes.len() == 1 {
+            return self.nodes.remove(0);
+        }
+
+        GenericNode {
+            tag_name: None,
+            value: None,
+            attributes: None,
+            within_special_tag: None,
+            children: self.nodes,
+            span,
+        }
+    }
+
+    /// Pushes a node to the stack of open elements, modifying it with the current node
+    fn push(&mut self, mut node: GenericNode<V>) {
+        if let Some(parent) = self.stack.last().or(self.context.as_ref()) {
+            modify_node_with_parent(&mut node, parent);
+        }
+        self.node_count += 1;
+        self.open_counts.update(&node, true);
+        self.stack.push(node);
+    }
+
+    /// Pops the current node from the stack of open elements
+    fn pop(&mut self) -> Option<GenericNode<V>> {
+        let node = self.stack.pop()?;
+        self.open_counts.update(&node, false);
+        Some(node)
+    }
+
+    /// Appends a node to the children of the current node, modifying it with the current node
+    fn append(&mut self, mut node: GenericNode<V>) {
+        self.node_count += 1;
+        if let Some(parent) = self.stack.last_mut() {
+            modify_node_with_parent(&mut node, parent);
+            parent.children.push(node);
+        } else {
+            if let Some(context) = &self.context {
+                modify_node_with_parent(&mut node, context);
+            }
+            self.nodes.push(node);
+        }
+    }
+
+    /// Adds a node that has been closed to the children of the current node
+    fn attach(&mut self, mut node: GenericNode<V>) {
+        self.end_span(&mut node);
+        match self.stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => self.nodes.push(node),
+        }
+    }
+
+    /// Ends the span of a node that is closed implicitly at the start of the current tag or text
+    fn end_span(&self, node: &mut GenericNode<V>) {
+        if let (Some(span), Some(position)) = (&mut node.span, self.position) {
+            span.end = position.start;
+        }
+    }
+
+    /// Returns an empty span at the start of the current tag or text for the elements reopened there
+    fn reopened_span(&self) -> Option<Span> {
+        self.position.map(|position| Span {
+            start: position.start,
+            end: position.start,
+        })
+    }
+
+    /// Closes the open element at `index` of the stack, along with all the elements opened after it
+    ///
+    /// The formatting elements closed implicitly are reopened before the next inline content.
+    fn close_from(&mut self, index: usize) {
+        let mut implicitly_closed = Vec::new();
+        while self.stack.len() > index + 1 {
+            if let Some(node) = self.pop() {
+                if node.tag_name.as_ref().is_some_and(is_formatting_element) {
+                    implicitly_closed.push(shallow_clone(&node));
+                }
+                self.attach(node);
+            }
+        }
+
+        let Some(node) = self.pop() else {
+            return;
+        };
+        let is_boundary = node.tag_name.as_ref().is_some_and(is_scope_boundary);
+        self.attach(node);
+
+        if is_boundary {
+            // formatting elements do not leak out of the boundaries of a scope (i.e. <td>)
+            return;
+        }
+        for node in implicitly_closed.into_iter().rev() {
+            self.push_active_formatting_element(node);
+        }
+    }
+
+    /// Adds a formatting element to the list of the elements to be reopened
+    fn push_active_formatting_element(&mut self, node: GenericNode<V>) {
+        let identical = |other: &GenericNode<V>| {
+            other.tag_name == node.tag_name && other.attributes == node.attributes
+        };
+        if self
+            .active_formatting_elements
+            .iter()
+            .filter(|other| identical(other))
+            .count()
+            >= NOAHS_ARK_LIMIT
+        {
+            if let Some(earliest) = self.active_formatting_elements.iter().position(identical) {
+                self.active_formatting_elements.remove(earliest);
+            }
+        }
+        self.active_formatting_elements.push(node);
+    }
+
+    /// Reopens the formatting elements that were closed implicitly
+    fn reconstruct_active_formatting_elements(&mut self) {
+        for mut node in std::mem::take(&mut self.active_formatting_elements) {
+            node.span = self.reopened_span();
+            self.push(node);
+        }
+    }
+
+    /// Returns the index of the innermost open element matching `predicate`,
+    /// unless a boundary of the scope (i.e. <td>) is open in between
+    fn find_in_scope(&self, scope: Scope, predicate: impl Fn(&NodeType) -> bool) -> Option<usize> {
+        for index in (0..self.stack.len()).rev() {
+            let tag = self.tag_at(index);
+            if predicate(tag) {
+                return Some(index);
+            }
+            if scope.is_boundary(tag) {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// Returns the tag of the open element at `index` of the stack
+    fn tag_at(&self, index: usize) -> &NodeType {
+        self.stack[index].tag_name.as_ref().unwrap_or(&Text)
+    }
+
+    /// Closes the innermost open element matching `predicate` in the scope, if there is any
+    fn close_in_scope(&mut self, scope: Scope, predicate: impl Fn(&NodeType) -> bool) -> bool {
+        match self.find_in_scope(scope, predicate) {
+            Some(index) => {
+                self.close_from(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Closes the innermost open `<p>` in the button scope, if there is any
+    ///
+    /// The stack is only searched when a `<p>` is open, since most of the start tags close a paragraph.
+    fn close_paragraph(&mut self) -> bool {
+        self.open_counts.paragraphs > 0 && self.close_in_scope(Scope::Button, |tag| tag == &P)
+    }
+
+    /// Closes the open elements whose closing tags can be omitted when the new element starts
+    /// (i.e. the first `<li>` in `<li>one<li>two`)
+    ///
+    /// See <https://html.spec.whatwg.org/multipage/syntax.html#optional-tags>
+    fn close_implied_elements(&mut self, node_type: &NodeType) {
+        match node_type {
+            Li => self.close_list_item(|tag| tag == &Li),
+            Unknown(tag) if tag == "dd" || tag == "dt" => self.close_list_item(is_definition),
+            Unknown(tag)
+                if (tag == "option" || tag == "optgroup")
+                    && self.stack.last().is_some_and(|node| {
+                        node.tag_name
+                            .as_ref()
+                            .is_some_and(|tag| tag.is_unknown("option"))
+                    }) =>
+            {
+                self.close_from(self.stack.len() - 1);
+            }
+            Unknown(tag) if tag == "td" || tag == "th" => {
+                self.close_in_scope(Scope::Table, is_table_cell);
+            }
+            Unknown(tag) if tag == "tr" => {
+                let _ = self.close_in_scope(Scope::Table, |tag| tag.is_unknown("tr"))
+                    || self.close_in_scope(Scope::Table, is_table_cell);
+            }
+            Unknown(tag) if tag == "thead" || tag == "tbody" || tag == "tfoot" => {
+                let _ = self.close_in_scope(Scope::Table, is_table_section)
+                    || self.close_in_scope(Scope::Table, |tag| tag.is_unknown("tr"))
+                    || self.close_in_scope(Scope::Table, is_table_cell);
+            }
+            _ => (),
+        }
+
+        if closes_paragraph(node_type) {
+            self.close_paragraph();
+        }
+
+        if is_heading(node_type)
+            && self
+                .stack
+                .last()
+                .is_some_and(|node| node.tag_name.as_ref().is_some_and(is_heading))
+        {
+            // headings cannot be nested
+            self.close_from(self.stack.len() - 1);
+        }
+    }
+
+    /// Closes the innermost open list item matching `predicate`, unless a block other than `<div>`, `<p>` or `<address>` is open in between
+    fn close_list_item(&mut self, predicate: impl Fn(&NodeType) -> bool) {
+        for index in (0..self.stack.len()).rev() {
+            let tag = self.tag_at(index);
+            if predicate(tag) {
+                self.close_from(index);
+                return;
+            }
+            if is_special_element(tag) && !matches!(tag, Div | P) && !tag.is_unknown("address") {
+                return;
+            }
+        }
+    }
+
+    /// Closes the formatting element `subject`, restructuring the tree when it is mis-nested
+    ///
+    /// See <https://html.spec.whatwg.org/multipage/parsing.html#adoption-agency-algorithm>
+    ///
+    /// Returns whether the formatting element was closed by the closing tag.
+    fn adoption_agency(&mut self, subject: &NodeType) -> bool {
+        for _ in 0..ADOPTION_AGENCY_OUTER_LOOP_LIMIT {
+            if let Some(index) = self
+                .active_formatting_elements
+                .iter()
+                .rposition(|node| node.tag_name.as_ref() == Some(subject))
+            {
+                // the formatting element is already closed, so it is just not reopened
+                self.active_formatting_elements.remove(index);
+                return false;
+            }
+
+            let Some(formatting_index) = self.find_in_scope(Scope::Default, |tag| tag == subject)
+            else {
+                return false;
+            };
+
+            let Some(furthest_block_index) =
+                (formatting_index + 1..self.stack.len()).find(|&index| {
+                    self.stack[index]
+                        .tag_name
+                        .as_ref()
+                        .is_some_and(is_special_element)
+                })
+            else {
+                // if there is no block inside the formatting element, it is closed along with the elements opened after it
+                self.close_from(formatting_index);
+                return true;
+            };
+
+            // split the stack into the formatting element, the elements in between, the furthest block and the rest
+            for node in &self.stack[formatting_index..] {
+                self.open_counts.update(node, false);
+            }
+            let mut above = self.stack.split_off(formatting_index).into_iter();
+            let Some(mut formatting_element) = above.next() else {
+                return false;
+            };
+            let in_between: Vec<GenericNode<V>> = above
+                .by_ref()
+                .take(furthest_block_index - formatting_index - 1)
+                .collect();
+            let Some(mut furthest_block) = above.next() else {
+                return false;
+            };
+            let rest: Vec<GenericNode<V>> = above.collect();
+
+            // the formatting elements in between are reopened around the furthest block
+            let mut reopened: Vec<GenericNode<V>> = in_between
+                .iter()
+                .filter(|node| node.tag_name.as_ref().is_some_and(is_formatting_element))
+                .rev()
+                .take(ADOPTION_AGENCY_INNER_LOOP_LIMIT)
+                .map(shallow_clone)
+                .collect();
+            reopened.reverse();
+
+            // the formatting element is closed with the elements in between, without the furthest block
+            if let Some(mut in_between) =
+                in_between
+                    .into_iter()
+                    .rev()
+                    .reduce(|mut child, mut parent| {
+                        self.end_span(&mut child);
+                        parent.children.push(child);
+                        parent
+                    })
+            {
+                self.end_span(&mut in_between);
+                formatting_element.children.push(in_between);
+            }
+            let mut new_formatting_element = shallow_clone(&formatting_element);
+            new_formatting_element.span = self.reopened_span();
+            self.attach(formatting_element);
+            for mut node in reopened {
+                node.span = self.reopened_span();
+                self.push(node);
+            }
+
+            // the children of the furthest block are moved into a copy of the formatting element
+            new_formatting_element.children = std::mem::take(&mut furthest_block.children);
+            self.open_counts.update(&furthest_block, true);
+            self.stack.push(furthest_block);
+            self.push(new_formatting_element);
+            for node in &rest {
+                self.open_counts.update(node, true);
+            }
+            self.stack.extend(rest);
+        }
+        false
+    }
+}
+
+/// The numbers of the open elements which most of the start tags look for in the stack,
+/// so that the stack is not searched for them when none is open (i.e. the `<p>` closed by a `<div>`)
+#[derive(Debug, Default, Clone, Copy)]
+struct OpenCounts {
+    /// the number of open `<p>` elements
+    paragraphs: usize,
+    /// the number of open `<a>` elements
+    links: usize,
+}
+
+impl OpenCounts {
+    /// Counts the node pushed to the stack of open elements, or popped from it
+    fn update<V>(&mut self, node: &GenericNode<V>, pushed: bool) {
+        let count = match node.tag_name {
+            Some(P) => &mut self.paragraphs,
+            Some(A) => &mut self.links,
+            _ => return,
+        };
+        if pushed {
+            *count += 1;
+        } else {
+            *count -= 1;
+        }
+    }
+}
+
+/// The scopes in which closing tags look for their open elements
+///
+/// See <https://html.spec.whatwg.org/multipage/parsing.html#has-an-element-in-scope>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Default,
+    /// the scope of `</li>`, which doesn't look outside of the innermost list
+    ListItem,
+    /// the scope of `</p>`, which doesn't look outside of the innermost button
+    Button,
+    /// the scope of table rows and cells, which doesn't look outside of the innermost table
+    Table,
+}
+
+impl Scope {
+    /// Checks whether the node is a boundary of the scope
+    fn is_boundary(&self, node_type: &NodeType) -> bool {
+        match self {
+            Scope::Default => is_scope_boundary(node_type),
+            Scope::ListItem => is_scope_boundary(node_type) || matches!(node_type, Ul | Ol),
+            Scope::Button => is_scope_boundary(node_type) || node_type.is_unknown("button"),
+            Scope::Table => {
+                matches!(node_type, Html)
+                    || node_type.is_unknown("table")
+                    || node_type.is_unknown("template")
+            }
+        }
+    }
+}
+
+/// Returns a copy of the node without its children, to be reopened in another place of the tree
+fn shallow_clone<V>(node: &GenericNode<V>) -> GenericNode<V> {
+    GenericNode {
+        tag_name: node.tag_name.clone(),
+        value: None,
+        attributes: node.attributes.clone(),
+        within_special_tag: None,
+        children: Vec::new(),
+        span: None,
+    }
+}
+
+/// Modifies a node with the parent's within_special_tag and tag type
+///
+/// # Arguments
+///
+/// * `node` - A mutable reference to a Node to be modified
+/// * `parent` - A reference to the parent Node
+pub(crate) fn modify_node_with_parent<V>(node: &mut GenericNode<V>, parent: &GenericNode<V>) {
+    if parent.within_special_tag.is_some() {
+        node.within_special_tag
+            .clone_from(&parent.within_special_tag)
+    }
+    if let Some(parent_tag_name) = &parent.tag_name {
+        if parent_tag_name.is_special_tag() {
+            if let Some(within_special_tag) = &mut node.within_special_tag {
+                within_special_tag.push(parent_tag_name.clone());
+            } else {
+                node.within_special_tag = Some(vec![parent_tag_name.clone()]);
+            }
+        }
+    }
+}
+
+/// Checks whether the node is a formatting element, which is reopened when it is mis-nested (i.e. `<b>`, `<em>`)
+fn is_formatting_element(node_type: &NodeType) -> bool {
+    match node_type {
+        A | Strong | Em | Code => true,
+        Unknown(tag) => matches!(
+            tag.as_str(),
+            "b" | "big" | "font" | "i" | "nobr" | "s" | "small" | "strike" | "tt" | "u"
+        ),
+        _ => false,
+    }
+}
+
+/// Checks whether the node is a part of a definition list item (i.e. `<dt>`, `<dd>`)
+fn is_definition(node_type: &NodeType) -> bool {
+    node_type.is_unknown("dd") || node_type.is_unknown("dt")
+}
+
+/// Checks whether the node is a table cell (i.e. `<td>`, `<th>`)
+fn is_table_cell(node_type: &NodeType) -> bool {
+    node_type.is_unknown("td") || node_type.is_unknown("th")
+}
+
+/// Checks whether the node is a section of a table (i.e. `<tbody>`)
+fn is_table_section(node_type: &NodeType) -> bool {
+    node_type.is_unknown("thead") || node_type.is_unknown("tbody") || node_type.is_unknown("tfoot")
+}
+
+/// Checks whether the start of the node closes an open paragraph (i.e. `<div>` in `<p>a<div>b</div>`)
+fn closes_paragraph(node_type: &NodeType) -> bool {
+    match node_type {
+        H1 | H2 | H3 | H4 | H5 | H6 | P | Div | Ul | Ol | Li | Pre | Hr | Blockquote => true,
+        Unknown(tag) => matches!(
+            tag.as_str(),
+            "address"
+                | "article"
+                | "aside"
+                | "center"
+                | "dd"
+                | "details"
+                | "dialog"
+                | "dir"
+                | "dl"
+                | "dt"
+                | "fieldset"
+                | "figcaption"
+                | "figure"
+                | "footer"
+                | "form"
+                | "header"
+                | "hgroup"
+                | "listing"
+                | "main"
+                | "menu"
+                | "nav"
+                | "plaintext"
+                | "search"
+                | "section"
+                | "summary"
+                | "table"
+                | "xmp"
+        ),
+        _ => false,
+    }
+}
+
+/// Checks whether the node is a heading (i.e. `<h1>`)
+fn is_heading(node_type: &NodeType) -> bool {
+    matches!(node_type, H1 | H2 | H3 | H4 | H5 | H6)
+}
+
+/// Checks whether the node is in the "special" category of HTML5, which mostly consists of block elements (i.e. `<div>`, `<li>`)
+///
+/// Closing tags of inline elements cannot close the elements outside of a special element.
+fn is_special_element(node_type: &NodeType) -> bool {
+    match node_type {
+        Html | Head | Style | Link | Script | Meta | Title | Body | H1 | H2 | H3 | H4 | H5 | H6
+        | P | Div | Ul | Ol | Li | Pre | Hr | Br | Blockquote => true,
+        Unknown(tag) => matches!(
+            tag.as_str(),
+            "address"
+                | "applet"
+                | "area"
+                | "article"
+                | "aside"
+                | "base"
+                | "basefont"
+                | "bgsound"
+                | "button"
+                | "caption"
+                | "center"
+                | "col"
+                | "colgroup"
+                | "dd"
+                | "details"
+                | "dir"
+                | "dl"
+                | "dt"
+                | "embed"
+                | "fieldset"
+                | "figcaption"
+                | "figure"
+                | "footer"
+                | "form"
+                | "frame"
+                | "frameset"
+                | "header"
+                | "hgroup"
+                | "iframe"
+                | "img"
+                | "input"
+                | "keygen"
+                | "listing"
+                | "main"
+                | "marquee"
+                | "menu"
+                | "nav"
+                | "noembed"
+                | "noframes"
+                | "noscript"
+                | "object"
+                | "param"
+                | "plaintext"
+                | "search"
+                | "section"
+                | "select"
+                | "source"
+                | "summary"
+                | "table"
+                | "tbody"
+                | "td"
+                | "template"
+                | "textarea"
+                | "tfoot"
+                | "th"
+                | "thead"
+                | "tr"
+                | "track"
+                | "wbr"
+                | "xmp"
+        ),
+        Foreign(..) => is_foreign_integration_point(node_type),
+        _ => false,
+    }
+}
+
+/// Checks whether the node is a boundary of the scope in which closing tags look for their open elements (i.e. `<td>`)
+fn is_scope_boundary(node_type: &NodeType) -> bool {
+    match node_type {
+        Html => true,
+        Unknown(tag) => matches!(
+            tag.as_str(),
+            "applet" | "caption" | "marquee" | "object" | "table" | "td" | "template" | "th"
+        ),
+        Foreign(..) => is_foreign_integration_point(node_type),
+        _ => false,
+    }
+}
+
+/// Checks whether the foreign element may contain HTML, which makes it special and a scope boundary like `<td>`
+fn is_foreign_integration_point(node_type: &NodeType) -> bool {
+    match node_type {
+        Foreign(Namespace::Svg, tag) => matches!(tag.as_str(), "foreignObject" | "desc" | "title"),
+        Foreign(Namespace::MathMl, tag) => {
+            tag == "annotation-xml" || is_mathml_text_integration_point(node_type)
+        }
+        _ => false,
+    }
+}
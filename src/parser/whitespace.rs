@@ -0,0 +1,191 @@
+//! This module contains the whitespace collapsing applied to the texts of a parsed tree.
+//!
+//! Texts are collapsed the way browsers render them with the CSS `white-space: normal` rule:
+//! each run of whitespace becomes a single space, a space following another space is removed even across inline elements (i.e. `<b>a </b> b`),
+//! and spaces at the start and end of a line, which is delimited by blocks (i.e. `<p>`) and line breaks, are removed.
+//! The texts inside `<pre>` and `<textarea>`, including `<code>` inside `<pre>`, are kept as they are.
+
+use super::tree_builder::TextValue;
+use crate::structs::{GenericNode, NodeType};
+
+/// The parts of the tree which affect the collapsing, in the order they appear in the HTML
+enum Event<'t, V> {
+    /// a text whose whitespace is collapsed
+    Text(&'t mut V),
+    /// the start or the end of a block, or a line break, which ends the current line
+    LineBreak,
+    /// a content which is not a text (i.e. `<img>`, `<textarea>`), which keeps the spaces around it
+    Content,
+}
+
+/// Collapses the whitespace of the texts in the nodes, and removes the texts which become empty
+pub(crate) fn collapse_whitespace<V: TextValue>(nodes: &mut Vec<GenericNode<V>>) {
+    let mut events = collect_events(nodes);
+
+    // the spaces to be removed from each text are found first, so that each text is collapsed only once
+    let mut trims = vec![(false, false); events.len()];
+    // at_line_start is true until a visible content is found in the line, so that leading spaces are removed
+    let mut at_line_start = true;
+    // trailing_space is the index of the text ending with a space, which is removed if the line ends right after it
+    let mut trailing_space = None;
+    for (index, event) in events.iter().enumerate() {
+        match event {
+            Event::Text(text) => {
+                let value = text.as_ref();
+                let trim_start = at_line_start || trailing_space.is_some();
+                trims[index].0 = trim_start;
+                // a text of only whitespace is a single space, unless it is removed as a leading space
+                if value.contains(|c| !is_collapsible(c)) || (!trim_start && !value.is_empty()) {
+                    at_line_start = false;
+                    trailing_space = value.ends_with(is_collapsible).then_some(index);
+                }
+            }
+            Event::LineBreak => {
+                if let Some(index) = trailing_space.take() {
+                    trims[index].1 = true;
+                }
+                at_line_start = true;
+            }
+            Event::Content => {
+                at_line_start = false;
+                trailing_space = None;
+            }
+        }
+    }
+
+    for (event, (trim_start, trim_end)) in events.iter_mut().zip(trims) {
+        if let Event::Text(text) = event {
+            collapse_text(&mut **text, trim_start, trim_end);
+        }
+    }
+
+    remove_empty_texts(nodes);
+}
+
+/// Lists the texts and the boundaries of lines in the nodes, skipping the elements which are not displayed (i.e. `<head>`)
+fn collect_events<V>(nodes: &mut [GenericNode<V>]) -> Vec<Event<'_, V>> {
+    let mut events = Vec::new();
+    // each entry is the iterator over the children of an element, with whether the element is a block
+    let mut stack = vec![(false, nodes.iter_mut())];
+    while let Some((is_block, children)) = stack.last_mut() {
+        let Some(node) = children.next() else {
+            if *is_block {
+                events.push(Event::LineBreak);
+            }
+            stack.pop();
+            continue;
+        };
+
+        let Some(tag_name) = &node.tag_name else {
+            // the root node without a tag name only groups its children
+            stack.push((false, node.children.iter_mut()));
+            continue;
+        };
+        match tag_name {
+            NodeType::Text => {
+                if let Some(value) = &mut node.value {
+                    events.push(Event::Text(value));
+                }
+            }
+            NodeType::Comment => (),
+            tag_name if is_hidden(tag_name) => (),
+            tag_name if is_whitespace_preserved(tag_name) => {
+                events.push(if tag_name.is_block_element() {
+                    Event::LineBreak
+                } else {
+                    Event::Content
+                })
+            }
+            NodeType::Br | NodeType::Hr => events.push(Event::LineBreak),
+            tag_name if tag_name.is_void_element() => events.push(Event::Content),
+            tag_name if tag_name.is_block_element() => {
+                events.push(Event::LineBreak);
+                stack.push((true, node.children.iter_mut()));
+            }
+            _ => stack.push((false, node.children.iter_mut())),
+        }
+    }
+    events
+}
+
+/// Collapses the runs of whitespace in the text into single spaces, removing the leading and the trailing ones if requested
+fn collapse_text<V: TextValue>(text: &mut V, trim_start: bool, trim_end: bool) {
+    let value = text.as_ref();
+    let mut start = 0;
+    let mut end = value.len();
+    if trim_start {
+        start = end - value.trim_start_matches(is_collapsible).len();
+    }
+    if trim_end {
+        end = start + value[start..].trim_end_matches(is_collapsible).len();
+    }
+    let rest = &value[start..end];
+
+    let mut previous_space = false;
+    let is_collapsed = rest.chars().all(|c| {
+        let is_valid = (c == ' ' && !previous_space) || !is_collapsible(c);
+        previous_space = c == ' ';
+        is_valid
+    });
+    if is_collapsed {
+        // the text only needs to be trimmed, which keeps a borrowed text borrowed
+        text.slice(start..end);
+        return;
+    }
+
+    let mut collapsed = String::with_capacity(rest.len());
+    for c in rest.chars() {
+        if !is_collapsible(c) {
+            collapsed.push(c);
+        } else if !collapsed.ends_with(' ') {
+            collapsed.push(' ');
+        }
+    }
+    text.replace(collapsed);
+}
+
+/// Removes the texts which became empty from the nodes and their descendants
+fn remove_empty_texts<V: TextValue>(nodes: &mut Vec<GenericNode<V>>) {
+    let is_empty_text = |node: &GenericNode<V>| {
+        node.tag_name == Some(NodeType::Text)
+            && node
+                .value
+                .as_ref()
+                .is_some_and(|value| value.as_ref().is_empty())
+    };
+    nodes.retain(|node| !is_empty_text(node));
+    let mut stack = nodes.iter_mut().collect::<Vec<_>>();
+    while let Some(node) = stack.pop() {
+        node.children.retain(|child| !is_empty_text(child));
+        stack.extend(node.children.iter_mut());
+    }
+}
+
+/// Checks whether the character is whitespace collapsed by CSS (space, tab, line feed, carriage return or form feed)
+fn is_collapsible(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0C')
+}
+
+/// Checks whether the element is not displayed, so that its texts are kept as they are and don't affect the texts around it
+fn is_hidden(node_type: &NodeType) -> bool {
+    use NodeType::*;
+    match node_type {
+        Head | Title | Meta | Link | Script | Style => true,
+        Unknown(tag) => matches!(
+            tag.as_str(),
+            "template" | "iframe" | "noembed" | "noframes" | "noscript"
+        ),
+        _ => false,
+    }
+}
+
+/// Checks whether the whitespace of the texts in the element is kept as it is (i.e. `<pre>`, `<textarea>`)
+fn is_whitespace_preserved(node_type: &NodeType) -> bool {
+    match node_type {
+        NodeType::Pre => true,
+        NodeType::Unknown(tag) => {
+            matches!(tag.as_str(), "textarea" | "listing" | "xmp" | "plaintext")
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,866 @@
+//! This module contains the Selector struct, which finds the nodes of a tree matching a CSS selector (i.e. `article.post-body > p`).
+//!
+//! The supported selectors are the type (i.e. `p`, `*`), class (`.note`), id (`#main`) and attribute selectors
+//! (`[href]`, `[href^=http]`, with the `=`, `~=`, `|=`, `^=`, `$=` and `*=` operators and the `i` flag),
+//! the descendant (` `), child (`>`), next sibling (`+`) and subsequent sibling (`~`) combinators,
+//! and the `:not()`, `:first-child` and `:nth-child()` pseudo-classes, in lists separated by commas (i.e. `nav, footer, .ads`).
+//!
+//! Only elements are matched, so texts and comments are neither matched nor counted as siblings.
+//! The type and attribute names of HTML elements are matched case-insensitively, and those of foreign elements (i.e. `linearGradient`, `viewBox`) case-sensitively.
+//! See <https://www.w3.org/TR/selectors-4/>
+
+use crate::structs::{AttributeValues, Attributes, GenericNode, Namespace, NodeType};
+use std::{borrow::Cow, collections::HashSet, fmt::Display, str::FromStr};
+
+/// The maximum number of `:not()` nested in each other, so that parsing and matching a selector can't overflow the stack
+const MAX_NESTING: usize = 32;
+
+/// Errors that will be returned when parsing a malformed or unsupported selector
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SelectorParseError {
+    /// The character at the offset is not expected there (i.e. the second `>` in `ul >> li`)
+    UnexpectedCharacter(usize),
+    /// The selector ends at the offset before it is complete (i.e. `ul >`)
+    UnexpectedEnd(usize),
+    /// The pseudo-class starting at the offset is not supported (i.e. `:hover`)
+    UnsupportedPseudoClass(usize),
+    /// The argument of `:nth-child()` starting at the offset is not of the form `An+B`, `odd` or `even`
+    InvalidNthChild(usize),
+    /// The `:not()` starting at the offset is nested too deeply in other `:not()`
+    TooDeeplyNested(usize),
+}
+
+impl SelectorParseError {
+    /// Returns the byte offset in the selector where the error occurred
+    pub fn offset(&self) -> usize {
+        match self {
+            SelectorParseError::UnexpectedCharacter(offset)
+            | SelectorParseError::UnexpectedEnd(offset)
+            | SelectorParseError::UnsupportedPseudoClass(offset)
+            | SelectorParseError::InvalidNthChild(offset)
+            | SelectorParseError::TooDeeplyNested(offset) => *offset,
+        }
+    }
+}
+
+impl Display for SelectorParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            SelectorParseError::UnexpectedCharacter(_) => "Unexpected character",
+            SelectorParseError::UnexpectedEnd(_) => "Unexpected end of the selector",
+            SelectorParseError::UnsupportedPseudoClass(_) => "Unsupported pseudo-class",
+            SelectorParseError::InvalidNthChild(_) => "Invalid argument of :nth-child()",
+            SelectorParseError::TooDeeplyNested(_) => "Too deeply nested :not()",
+        };
+        write!(f, "{} at offset {} of the selector", message, self.offset())
+    }
+}
+
+impl std::error::Error for SelectorParseError {}
+
+/// Represents a parsed CSS selector list (i.e. `nav, footer, .ads`), which matches an element if any of its selectors does.
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::{parser::safe_parse_html, selector::Selector};
+///
+/// let input = "<div><nav>menu</nav><article class=\"post-body\"><p>one</p><p>two</p></article></div>";
+/// let mut node = safe_parse_html(input.to_string()).unwrap();
+///
+/// let paragraphs = node.select(&Selector::parse("article.post-body > p").unwrap());
+/// assert_eq!(paragraphs.len(), 2);
+///
+/// let removed = node.remove_matching(&Selector::parse("nav, footer, .ads").unwrap());
+/// assert_eq!(removed.len(), 1);
+/// assert_eq!(node.children.len(), 1);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Selector {
+    selectors: Vec<ComplexSelector>,
+}
+
+impl Selector {
+    /// Parses a selector list
+    ///
+    /// Returns an error if the selector is malformed, or uses a selector or pseudo-class which is not supported.
+    pub fn parse(selector: &str) -> Result<Self, SelectorParseError> {
+        let mut parser = SelectorParser {
+            input: selector,
+            index: 0,
+            nesting: 0,
+        };
+        parser.parse_list()
+    }
+
+    /// Checks whether the node matches the selector, looking at the node alone as if it had no parent or siblings
+    pub fn matches<V>(&self, node: &GenericNode<V>) -> bool {
+        is_element(node)
+            && self.matches_element(
+                Element {
+                    ancestors: &[],
+                    node,
+                    index: 0,
+                },
+                &mut Failed::new(),
+            )
+    }
+
+    /// Checks whether the element matches any of the selectors in the list,
+    /// remembering the compound selectors which failed to match in `failed` for the next elements in the same tree
+    fn matches_element<V>(&self, element: Element<'_, '_, V>, failed: &mut Failed<V>) -> bool {
+        self.selectors
+            .iter()
+            .enumerate()
+            .any(|(selector, complex)| complex.matches(element, selector, failed))
+    }
+}
+
+/// The compound selectors which failed to match from an element, each with the index of its complex selector in the list,
+/// its own index in the complex selector and the element
+///
+/// Whether they match only depends on where the element is in the tree, so it is kept while walking the same tree.
+type Failed<V> = HashSet<(usize, usize, *const GenericNode<V>)>;
+
+impl FromStr for Selector {
+    type Err = SelectorParseError;
+
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        Selector::parse(selector)
+    }
+}
+
+/// A selector made of compound selectors joined by combinators (i.e. `ul > li.done`)
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct ComplexSelector {
+    compounds: Vec<CompoundSelector>,
+    /// the combinator between each compound selector and the next one, so there is one fewer than the compound selectors
+    combinators: Vec<Combinator>,
+}
+
+impl ComplexSelector {
+    /// Checks whether the element matches the last compound selector, along with the compound selectors before it,
+    /// which are matched from right to left
+    ///
+    /// The candidates for each compound selector are searched from a stack instead of recursion,
+    /// and the compound selectors which failed to match from an element are remembered in `failed`,
+    /// so that a long chain of descendant combinators doesn't search the same ancestors over and over.
+    fn matches<'p, 'n, V>(
+        &self,
+        element: Element<'p, 'n, V>,
+        selector: usize,
+        failed: &mut Failed<V>,
+    ) -> bool {
+        let last = self.compounds.len() - 1;
+        if !self.compounds[last].matches(element) {
+            return false;
+        }
+        if last == 0 {
+            return true;
+        }
+        // each entry is the index of a compound selector, along with the element it matched and the candidates for the one before it
+        let mut stack = vec![(last, element, self.candidates(last, element))];
+        while let Some((index, element, candidates)) = stack.last_mut() {
+            let Some(candidate) = candidates.next() else {
+                failed.insert((selector, *index, std::ptr::from_ref(element.node)));
+                stack.pop();
+                continue;
+            };
+            let previous = *index - 1;
+            let key = (selector, previous, std::ptr::from_ref(candidate.node));
+            if failed.contains(&key) {
+                continue;
+            }
+            if !self.compounds[previous].matches(candidate) {
+                failed.insert(key);
+                continue;
+            }
+            if previous == 0 {
+                return true;
+            }
+            stack.push((previous, candidate, self.candidates(previous, candidate)));
+        }
+        false
+    }
+
+    /// Returns the elements which the compound selector before the one at `index` can match,
+    /// given the element matched by the compound selector at `index`
+    fn candidates<'p, 'n, V>(
+        &self,
+        index: usize,
+        element: Element<'p, 'n, V>,
+    ) -> Box<dyn Iterator<Item = Element<'p, 'n, V>> + 'p> {
+        match self.combinators[index - 1] {
+            Combinator::Descendant => {
+                Box::new(std::iter::successors(element.parent(), Element::parent))
+            }
+            Combinator::Child => Box::new(element.parent().into_iter()),
+            Combinator::NextSibling => Box::new(element.previous_siblings().take(1)),
+            Combinator::SubsequentSibling => Box::new(element.previous_siblings()),
+        }
+    }
+}
+
+/// The relation between two elements matched by the compound selectors around it
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Combinator {
+    /// ` `, the left element is an ancestor of the right element
+    Descendant,
+    /// `>`, the left element is the parent of the right element
+    Child,
+    /// `+`, the left element is right before the right element
+    NextSibling,
+    /// `~`, the left element is before the right element
+    SubsequentSibling,
+}
+
+/// A selector matching a single element (i.e. `li.done:not(:first-child)`)
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct CompoundSelector {
+    /// the type selector, which is None for the universal selector (`*`) or when it is left out
+    type_name: Option<String>,
+    conditions: Vec<Condition>,
+}
+
+impl CompoundSelector {
+    fn matches<V>(&self, element: Element<'_, '_, V>) -> bool {
+        let Some(tag_name) = &element.node.tag_name else {
+            return false;
+        };
+        if let Some(type_name) = &self.type_name {
+            let matches_type = match tag_name.namespace() {
+                Namespace::Html => tag_name.tag_str().eq_ignore_ascii_case(type_name),
+                _ => tag_name.tag_str() == type_name,
+            };
+            if !matches_type {
+                return false;
+            }
+        }
+        let attributes = element.node.attributes.as_ref();
+        self.conditions.iter().all(|condition| match condition {
+            Condition::Class(class) => attributes
+                .and_then(Attributes::get_class)
+                .is_some_and(|classes| classes.split_ascii_whitespace().any(|name| name == class)),
+            Condition::Id(id) => attributes
+                .and_then(Attributes::get_id)
+                .is_some_and(|value| value == id),
+            Condition::Attribute(selector) => attributes
+                .and_then(|attributes| attribute_value(attributes, &selector.name))
+                .is_some_and(|value| selector.matches(&value)),
+            Condition::Not(selector) => !selector.matches_element(element, &mut Failed::new()),
+            Condition::NthChild { a, b } => {
+                let position = element.position();
+                match a {
+                    0 => position == *b,
+                    _ => (position - b) % a == 0 && (position - b) / a >= 0,
+                }
+            }
+        })
+    }
+}
+
+/// A condition on an element other than its type
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Condition {
+    /// `.class`
+    Class(String),
+    /// `#id`
+    Id(String),
+    /// `[name]` or `[name=value]`
+    Attribute(AttributeSelector),
+    /// `:not(selector)`
+    Not(Selector),
+    /// `:nth-child(An+B)`, which is `:nth-child(1)` for `:first-child`
+    NthChild { a: i64, b: i64 },
+}
+
+/// An attribute selector (i.e. `[href^="http" i]`)
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct AttributeSelector {
+    name: String,
+    /// the operator comparing the attribute value with `value`, which is None when only the presence of the attribute is checked
+    operator: Option<AttributeOperator>,
+    value: String,
+    case_insensitive: bool,
+}
+
+impl AttributeSelector {
+    fn matches(&self, value: &str) -> bool {
+        let Some(operator) = self.operator else {
+            return true;
+        };
+        let (value, expected) = if self.case_insensitive {
+            (
+                Cow::Owned(value.to_ascii_lowercase()),
+                Cow::Owned(self.value.to_ascii_lowercase()),
+            )
+        } else {
+            (Cow::Borrowed(value), Cow::Borrowed(self.value.as_str()))
+        };
+        let (value, expected) = (value.as_ref(), expected.as_ref());
+        // the operators other than `=` and `|=` never match an empty value
+        match operator {
+            AttributeOperator::Equals => value == expected,
+            AttributeOperator::Includes => {
+                !expected.is_empty()
+                    && !expected.contains(|c: char| c.is_ascii_whitespace())
+                    && value.split_ascii_whitespace().any(|word| word == expected)
+            }
+            AttributeOperator::DashMatch => {
+                value == expected
+                    || value
+                        .strip_prefix(expected)
+                        .is_some_and(|rest| rest.starts_with('-'))
+            }
+            AttributeOperator::Prefix => !expected.is_empty() && value.starts_with(expected),
+            AttributeOperator::Suffix => !expected.is_empty() && value.ends_with(expected),
+            AttributeOperator::Substring => !expected.is_empty() && value.contains(expected),
+        }
+    }
+}
+
+/// The operators of the attribute selectors
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum AttributeOperator {
+    /// `=`, the value is exactly the one given
+    Equals,
+    /// `~=`, the value is a list of words separated by whitespace, one of which is the one given
+    Includes,
+    /// `|=`, the value is the one given or starts with it followed by `-` (i.e. `en-US` for `[lang|=en]`)
+    DashMatch,
+    /// `^=`, the value starts with the one given
+    Prefix,
+    /// `$=`, the value ends with the one given
+    Suffix,
+    /// `*=`, the value contains the one given
+    Substring,
+}
+
+/// Returns the value of the attribute as a string, where a true boolean attribute (i.e. `checked`) has an empty value
+///
+/// The name is matched exactly first for the case-sensitive attributes of foreign elements (i.e. `viewBox`), then in lowercase.
+fn attribute_value<'a>(attributes: &'a Attributes, name: &str) -> Option<Cow<'a, str>> {
+    if name.eq_ignore_ascii_case("id") {
+        return attributes.id.as_deref().map(Cow::Borrowed);
+    }
+    if name.eq_ignore_ascii_case("class") {
+        return attributes.class.as_deref().map(Cow::Borrowed);
+    }
+    let value = attributes
+        .attributes
+        .get(name)
+        .or_else(|| attributes.attributes.get(&name.to_ascii_lowercase()))?;
+    match value {
+        AttributeValues::String(value) => Some(Cow::Borrowed(value)),
+        AttributeValues::Number(value) => Some(Cow::Owned(value.to_string())),
+        AttributeValues::Bool(true) => Some(Cow::Borrowed("")),
+        AttributeValues::Bool(false) => None,
+    }
+}
+
+/// Checks whether the node is an element, as opposed to a text, a comment or the root node without a tag name
+fn is_element<V>(node: &GenericNode<V>) -> bool {
+    matches!(&node.tag_name, Some(tag_name) if !matches!(tag_name, NodeType::Text | NodeType::Comment))
+}
+
+/// An element being matched, along with its ancestors to find its parent and siblings
+struct Element<'p, 'n, V> {
+    /// the ancestors of the element from the root, each with its index in the children of its parent
+    ancestors: &'p [(&'n GenericNode<V>, usize)],
+    node: &'n GenericNode<V>,
+    /// the index of the element in the children of its parent
+    index: usize,
+}
+
+impl<V> Clone for Element<'_, '_, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for Element<'_, '_, V> {}
+
+impl<'p, 'n, V> Element<'p, 'n, V> {
+    /// Returns the parent of the element, which is None for the root and the top-level elements under a root without a tag name
+    fn parent(&self) -> Option<Self> {
+        let ((node, index), ancestors) = self.ancestors.split_last()?;
+        is_element(node).then_some(Element {
+            ancestors,
+            node,
+            index: *index,
+        })
+    }
+
+    /// Returns the element and its siblings, which is only the element itself for the root
+    fn siblings(&self) -> &'n [GenericNode<V>] {
+        match self.ancestors.last() {
+            Some((parent, _)) => &parent.children,
+            None => std::slice::from_ref(self.node),
+        }
+    }
+
+    /// Returns the elements before the element in its parent, from the closest one
+    fn previous_siblings(self) -> impl Iterator<Item = Self> {
+        self.siblings()[..self.index]
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, sibling)| is_element(sibling))
+            .map(move |(index, node)| Element {
+                ancestors: self.ancestors,
+                node,
+                index,
+            })
+    }
+
+    /// Returns the position of the element among the elements in its parent, starting from 1
+    fn position(&self) -> i64 {
+        let previous = self.siblings()[..self.index]
+            .iter()
+            .filter(|sibling| is_element(sibling))
+            .count();
+        previous as i64 + 1
+    }
+}
+
+/// What `walk` does after visiting a node
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Visit {
+    /// visit the children of the node, then the nodes after it
+    Continue,
+    /// skip the children of the node
+    SkipChildren,
+    /// stop walking
+    Stop,
+}
+
+/// Visits the node and its descendants in the order they appear in the HTML,
+/// calling `visit` with the path from the root to each node, along with whether the node matches the selector
+///
+/// The path holds each node with its index in the children of its parent, and the node itself is the last one.
+fn walk<'n, V>(
+    root: &'n GenericNode<V>,
+    selector: &Selector,
+    mut visit: impl FnMut(&[(&'n GenericNode<V>, usize)], bool) -> Visit,
+) {
+    let mut path = Vec::new();
+    let mut failed = Failed::new();
+    // each entry is a node along with its depth and its index in the children of its parent
+    let mut stack = vec![(root, 0, 0)];
+    while let Some((node, depth, index)) = stack.pop() {
+        path.truncate(depth);
+        path.push((node, index));
+        let matched = is_element(node)
+            && selector.matches_element(
+                Element {
+                    ancestors: &path[..depth],
+                    node,
+                    index,
+                },
+                &mut failed,
+            );
+        match visit(&path, matched) {
+            Visit::Continue => stack.extend(
+                node.children
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(index, child)| (child, depth + 1, index)),
+            ),
+            Visit::SkipChildren => {}
+            Visit::Stop => return,
+        }
+    }
+}
+
+impl<V> GenericNode<V> {
+    /// Returns the node and its descendants which match the selector, in the order they appear in the HTML
+    ///
+    /// The node is the root of the tree being matched, so the selector can't look at its ancestors.
+    pub fn select(&self, selector: &Selector) -> Vec<&GenericNode<V>> {
+        let mut res = Vec::new();
+        walk(self, selector, |path, matched| {
+            if matched {
+                res.extend(path.last().map(|(node, _)| *node));
+            }
+            Visit::Continue
+        });
+        res
+    }
+
+    /// Returns the first node, either the node itself or one of its descendants, which matches the selector
+    pub fn select_first(&self, selector: &Selector) -> Option<&GenericNode<V>> {
+        let mut res = None;
+        walk(self, selector, |path, matched| {
+            if !matched {
+                return Visit::Continue;
+            }
+            res = path.last().map(|(node, _)| *node);
+            Visit::Stop
+        });
+        res
+    }
+
+    /// Returns mutable references to the node and its descendants which match the selector, in the order they appear in the HTML
+    ///
+    /// The matches inside another match are left out, since they can be reached from it,
+    /// so the outermost match is returned for nested matches (i.e. only the outer `<div>` of `<div><div></div></div>` for `div`).
+    pub fn select_mut(&mut self, selector: &Selector) -> Vec<&mut GenericNode<V>> {
+        // the nodes are visited again in the same order, skipping the children of the matches the same way
+        let mut visits = Vec::new();
+        walk(self, selector, |_, matched| {
+            visits.push(matched);
+            if matched {
+                Visit::SkipChildren
+            } else {
+                Visit::Continue
+            }
+        });
+
+        let mut res = Vec::new();
+        let mut visits = visits.into_iter();
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            if visits.next().unwrap_or(false) {
+                res.push(node);
+            } else {
+                stack.extend(node.children.iter_mut().rev());
+            }
+        }
+        res
+    }
+
+    /// Removes the descendants of the node which match the selector, and returns them in the order they appeared in the HTML
+    ///
+    /// The node itself is never removed. The matches inside another match are removed along with it.
+    /// All of the matches are found before any of them is removed, so removing a node doesn't change what its siblings match (i.e. `li:first-child`).
+    pub fn remove_matching(&mut self, selector: &Selector) -> Vec<GenericNode<V>> {
+        // the path of each match as the indices of the nodes in the children of their parents, without the root
+        let mut paths = Vec::new();
+        walk(self, selector, |path, matched| {
+            if !matched || path.len() == 1 {
+                return Visit::Continue;
+            }
+            paths.push(
+                path[1..]
+                    .iter()
+                    .map(|(_, index)| *index)
+                    .collect::<Vec<_>>(),
+            );
+            Visit::SkipChildren
+        });
+
+        // the matches are removed from the last one, so that the indices in the paths of the others stay the same
+        let mut removed = Vec::new();
+        for path in paths.iter().rev() {
+            let Some((index, parents)) = path.split_last() else {
+                continue;
+            };
+            let parent = parents
+                .iter()
+                .fold(&mut *self, |node, index| &mut node.children[*index]);
+            removed.push(parent.children.remove(*index));
+        }
+        removed.reverse();
+        removed
+    }
+}
+
+/// Parses a selector list from `input`, keeping track of the index of the next character to read
+struct SelectorParser<'s> {
+    input: &'s str,
+    index: usize,
+    /// the number of `:not()` the parser is in
+    nesting: usize,
+}
+
+impl SelectorParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.index..].chars().next()
+    }
+
+    fn unexpected(&self) -> SelectorParseError {
+        match self.peek() {
+            Some(_) => SelectorParseError::UnexpectedCharacter(self.index),
+            None => SelectorParseError::UnexpectedEnd(self.index),
+        }
+    }
+
+    /// Skips the whitespace at the index, returning whether there was any
+    fn skip_whitespace(&mut self) -> bool {
+        let rest = &self.input[self.index..];
+        let trimmed = rest.trim_start_matches(|c: char| c.is_ascii_whitespace());
+        self.index += rest.len() - trimmed.len();
+        trimmed.len() != rest.len()
+    }
+
+    /// Consumes the character if it is the next one
+    fn eat(&mut self, char: char) -> bool {
+        if self.peek() == Some(char) {
+            self.index += char.len_utf8();
+            return true;
+        }
+        false
+    }
+
+    /// Parses a comma separated list of selectors, up to the end of the input or the `)` closing a `:not(`
+    fn parse_list(&mut self) -> Result<Selector, SelectorParseError> {
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.parse_complex()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.index += 1,
+                Some(')') if self.nesting > 0 => break,
+                None if self.nesting == 0 => break,
+                _ => return Err(self.unexpected()),
+            }
+        }
+        Ok(Selector { selectors })
+    }
+
+    fn parse_complex(&mut self) -> Result<ComplexSelector, SelectorParseError> {
+        let mut compounds = vec![self.parse_compound()?];
+        let mut combinators = Vec::new();
+        loop {
+            let has_whitespace = self.skip_whitespace();
+            let combinator = match self.peek() {
+                Some('>') => Combinator::Child,
+                Some('+') => Combinator::NextSibling,
+                Some('~') => Combinator::SubsequentSibling,
+                Some(',' | ')') | None => break,
+                Some(_) if has_whitespace => Combinator::Descendant,
+                Some(_) => return Err(self.unexpected()),
+            };
+            if combinator != Combinator::Descendant {
+                self.index += 1;
+                self.skip_whitespace();
+            }
+            combinators.push(combinator);
+            compounds.push(self.parse_compound()?);
+        }
+        Ok(ComplexSelector {
+            compounds,
+            combinators,
+        })
+    }
+
+    fn parse_compound(&mut self) -> Result<CompoundSelector, SelectorParseError> {
+        let start = self.index;
+        let type_name = match self.peek() {
+            Some('*') => {
+                self.index += 1;
+                None
+            }
+            Some(c) if is_name_char(c) && !c.is_ascii_digit() => Some(self.parse_name()?),
+            _ => None,
+        };
+
+        let mut conditions = Vec::new();
+        loop {
+            let condition = match self.peek() {
+                Some('.') => {
+                    self.index += 1;
+                    Condition::Class(self.parse_name()?)
+                }
+                Some('#') => {
+                    self.index += 1;
+                    Condition::Id(self.parse_name()?)
+                }
+                Some('[') => {
+                    self.index += 1;
+                    Condition::Attribute(self.parse_attribute()?)
+                }
+                Some(':') => {
+                    self.index += 1;
+                    self.parse_pseudo_class()?
+                }
+                _ => break,
+            };
+            conditions.push(condition);
+        }
+
+        if self.index == start {
+            return Err(self.unexpected());
+        }
+        Ok(CompoundSelector {
+            type_name,
+            conditions,
+        })
+    }
+
+    /// Parses an identifier (i.e. `post-body`), where a backslash escapes the character after it (i.e. `md\:flex`)
+    fn parse_name(&mut self) -> Result<String, SelectorParseError> {
+        let mut name = String::new();
+        while let Some(char) = self.peek() {
+            if char == '\\' {
+                self.index += 1;
+                let Some(escaped) = self.peek() else {
+                    return Err(self.unexpected());
+                };
+                self.index += escaped.len_utf8();
+                name.push(escaped);
+            } else if is_name_char(char) {
+                self.index += char.len_utf8();
+                name.push(char);
+            } else {
+                break;
+            }
+        }
+        if name.is_empty() {
+            return Err(self.unexpected());
+        }
+        Ok(name)
+    }
+
+    /// Parses an attribute selector after its `[`, up to and including its `]`
+    fn parse_attribute(&mut self) -> Result<AttributeSelector, SelectorParseError> {
+        self.skip_whitespace();
+        let name = self.parse_name()?;
+        self.skip_whitespace();
+        let mut selector = AttributeSelector {
+            name,
+            operator: None,
+            value: String::new(),
+            case_insensitive: false,
+        };
+        if self.eat(']') {
+            return Ok(selector);
+        }
+
+        let operator = match self.peek() {
+            Some('=') => AttributeOperator::Equals,
+            Some('~') => AttributeOperator::Includes,
+            Some('|') => AttributeOperator::DashMatch,
+            Some('^') => AttributeOperator::Prefix,
+            Some('$') => AttributeOperator::Suffix,
+            Some('*') => AttributeOperator::Substring,
+            _ => return Err(self.unexpected()),
+        };
+        if operator != AttributeOperator::Equals {
+            self.index += 1;
+        }
+        if !self.eat('=') {
+            return Err(self.unexpected());
+        }
+        selector.operator = Some(operator);
+
+        self.skip_whitespace();
+        selector.value = match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.index += 1;
+                self.parse_quoted(quote)?
+            }
+            _ => self.parse_name()?,
+        };
+
+        self.skip_whitespace();
+        if self.peek().is_some_and(is_name_char) {
+            let flag_start = self.index;
+            selector.case_insensitive = match self.parse_name()?.as_str() {
+                "i" | "I" => true,
+                "s" | "S" => false,
+                _ => return Err(SelectorParseError::UnexpectedCharacter(flag_start)),
+            };
+            self.skip_whitespace();
+        }
+        if !self.eat(']') {
+            return Err(self.unexpected());
+        }
+        Ok(selector)
+    }
+
+    /// Parses a quoted string after its opening quotation mark, up to and including its closing one
+    fn parse_quoted(&mut self, quote: char) -> Result<String, SelectorParseError> {
+        let mut value = String::new();
+        loop {
+            let Some(char) = self.peek() else {
+                return Err(self.unexpected());
+            };
+            self.index += char.len_utf8();
+            match char {
+                _ if char == quote => return Ok(value),
+                '\\' => {
+                    let Some(escaped) = self.peek() else {
+                        return Err(self.unexpected());
+                    };
+                    self.index += escaped.len_utf8();
+                    value.push(escaped);
+                }
+                _ => value.push(char),
+            }
+        }
+    }
+
+    /// Parses a pseudo-class after its `:`
+    fn parse_pseudo_class(&mut self) -> Result<Condition, SelectorParseError> {
+        // the offset of the `:`
+        let start = self.index - 1;
+        let name = self.parse_name()?.to_ascii_lowercase();
+        match name.as_str() {
+            "first-child" => Ok(Condition::NthChild { a: 0, b: 1 }),
+            "not" => {
+                if !self.eat('(') {
+                    return Err(self.unexpected());
+                }
+                if self.nesting == MAX_NESTING {
+                    return Err(SelectorParseError::TooDeeplyNested(start));
+                }
+                self.nesting += 1;
+                let selector = self.parse_list()?;
+                self.nesting -= 1;
+                self.index += 1; // the `)` closing the list
+                Ok(Condition::Not(selector))
+            }
+            "nth-child" => {
+                if !self.eat('(') {
+                    return Err(self.unexpected());
+                }
+                let argument_start = self.index;
+                let Some(length) = self.input[argument_start..].find(')') else {
+                    return Err(SelectorParseError::UnexpectedEnd(self.input.len()));
+                };
+                self.index += length + 1;
+                let (a, b) = parse_nth(&self.input[argument_start..argument_start + length])
+                    .ok_or(SelectorParseError::InvalidNthChild(argument_start))?;
+                Ok(Condition::NthChild { a, b })
+            }
+            _ => Err(SelectorParseError::UnsupportedPseudoClass(start)),
+        }
+    }
+}
+
+/// Checks whether the character can be a part of an identifier
+fn is_name_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || matches!(char, '-' | '_' | '\\') || !char.is_ascii()
+}
+
+/// Parses the argument of `:nth-child()` (i.e. `2n+1`, `odd`, `-n + 3`) into A and B of `An+B`
+fn parse_nth(argument: &str) -> Option<(i64, i64)> {
+    let argument = argument
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match argument.as_str() {
+        "odd" => return Some((2, 1)),
+        "even" => return Some((2, 0)),
+        _ => {}
+    }
+    let parse_number = |number: &str| number.parse::<i32>().ok().map(i64::from);
+    let Some((a, b)) = argument.split_once('n') else {
+        return Some((0, parse_number(&argument)?));
+    };
+    let a = match a {
+        "" | "+" => 1,
+        "-" => -1,
+        _ => parse_number(a)?,
+    };
+    let b = match b {
+        "" => 0,
+        _ if b.starts_with(['+', '-']) => parse_number(b)?,
+        _ => return None,
+    };
+    Some((a, b))
+}
@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use crate::parser::ParseWarning;
+use std::{borrow::Cow, collections::HashMap};
 
 /// Represents the different types of HTML elements that the library supports.
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
@@ -34,6 +35,90 @@ pub enum NodeType {
     Text,
     Comment,
     Unknown(String),
+    /// An element inside `<svg>` or `<math>`, with its namespace and its case-sensitive name (i.e. `linearGradient`)
+    Foreign(Namespace, String),
+}
+
+/// Represents the namespace of an element, which is the HTML namespace except for inline SVG and MathML,
+/// or the namespace of a prefixed attribute (i.e. `xlink:href`)
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash)]
+pub enum Namespace {
+    #[default]
+    Html,
+    Svg,
+    MathMl,
+    XLink,
+    Xml,
+    XmlNs,
+}
+
+impl Namespace {
+    /// Returns the URL which identifies the namespace (i.e. `http://www.w3.org/2000/svg`)
+    pub fn url(&self) -> &'static str {
+        match self {
+            Namespace::Html => "http://www.w3.org/1999/xhtml",
+            Namespace::Svg => "http://www.w3.org/2000/svg",
+            Namespace::MathMl => "http://www.w3.org/1998/Math/MathML",
+            Namespace::XLink => "http://www.w3.org/1999/xlink",
+            Namespace::Xml => "http://www.w3.org/XML/1998/namespace",
+            Namespace::XmlNs => "http://www.w3.org/2000/xmlns/",
+        }
+    }
+}
+
+/// Represents the qualified name of an element or an attribute, split into its prefix and its local name
+///
+/// HTML doesn't declare namespaces for prefixes, so a prefixed element (i.e. `<o:p>` from Word) stays in the namespace
+/// of its parent like browsers do, and only the well-known `xlink`, `xml` and `xmlns` prefixes of attributes have a namespace.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct QualName {
+    /// The prefix before the colon (i.e. `ac` of `ac:structured-macro`), if there is any
+    pub prefix: Option<String>,
+    /// The name without the prefix (i.e. `structured-macro`)
+    pub local: String,
+    /// The namespace of the element, or of the attribute if its prefix is a well-known one
+    pub namespace: Option<Namespace>,
+}
+
+impl QualName {
+    /// Creates a new QualName from prefix, local name and namespace
+    pub fn new(prefix: Option<String>, local: String, namespace: Option<Namespace>) -> Self {
+        QualName {
+            prefix,
+            local,
+            namespace,
+        }
+    }
+
+    /// Splits the name of an attribute (i.e. `xlink:href`) into a QualName, resolving the well-known prefixes
+    pub fn attribute(name: &str) -> Self {
+        let (prefix, local) = split_prefix(name);
+        let namespace = match prefix {
+            Some("xlink") => Some(Namespace::XLink),
+            Some("xml") => Some(Namespace::Xml),
+            Some("xmlns") => Some(Namespace::XmlNs),
+            None if local == "xmlns" => Some(Namespace::XmlNs),
+            _ => None,
+        };
+        QualName::new(prefix.map(String::from), local.to_string(), namespace)
+    }
+}
+
+impl std::fmt::Display for QualName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.prefix {
+            Some(prefix) => write!(f, "{}:{}", prefix, self.local),
+            None => write!(f, "{}", self.local),
+        }
+    }
+}
+
+/// Splits a name at its first colon into the prefix and the local name, unless either of them would be empty
+fn split_prefix(name: &str) -> (Option<&str>, &str) {
+    match name.split_once(':') {
+        Some((prefix, local)) if !prefix.is_empty() && !local.is_empty() => (Some(prefix), local),
+        _ => (None, name),
+    }
 }
 
 impl NodeType {
@@ -42,6 +127,170 @@ impl NodeType {
         matches!(self, Blockquote | Ul | Ol)
     }
 
+    /// Checks whether the node is an unknown tag with the name passed in
+    pub fn is_unknown(&self, tag: &str) -> bool {
+        matches!(self, NodeType::Unknown(unknown) if unknown == tag)
+    }
+
+    /// Checks whether the node is an HTML void element, which never has children or a closing tag (i.e. `<br>`, `<img>`)
+    pub fn is_void_element(&self) -> bool {
+        use NodeType::*;
+        match self {
+            Link | Meta | Hr | Br => true,
+            Unknown(tag) => matches!(
+                tag.as_str(),
+                "area"
+                    | "base"
+                    | "basefont"
+                    | "bgsound"
+                    | "col"
+                    | "embed"
+                    | "frame"
+                    | "img"
+                    | "input"
+                    | "keygen"
+                    | "param"
+                    | "source"
+                    | "track"
+                    | "wbr"
+            ),
+            _ => false,
+        }
+    }
+
+    /// Checks whether the node is an element displayed as a block (i.e. `<p>`, `<li>`), which starts and ends a line of text
+    pub fn is_block_element(&self) -> bool {
+        use NodeType::*;
+        match self {
+            Html | Body | Div | P | H1 | H2 | H3 | H4 | H5 | H6 | Ul | Ol | Li | Pre | Hr
+            | Blockquote => true,
+            Unknown(tag) => matches!(
+                tag.as_str(),
+                "address"
+                    | "article"
+                    | "aside"
+                    | "caption"
+                    | "center"
+                    | "dd"
+                    | "details"
+                    | "dialog"
+                    | "dir"
+                    | "dl"
+                    | "dt"
+                    | "fieldset"
+                    | "figcaption"
+                    | "figure"
+                    | "footer"
+                    | "form"
+                    | "header"
+                    | "hgroup"
+                    | "legend"
+                    | "listing"
+                    | "main"
+                    | "menu"
+                    | "nav"
+                    | "plaintext"
+                    | "search"
+                    | "section"
+                    | "summary"
+                    | "table"
+                    | "tbody"
+                    | "td"
+                    | "tfoot"
+                    | "th"
+                    | "thead"
+                    | "tr"
+                    | "xmp"
+            ),
+            _ => false,
+        }
+    }
+
+    /// Returns the tag name of the element (i.e. `h1`), which is `#text` for texts and `#comment` for comments
+    pub fn tag_str(&self) -> &str {
+        use NodeType::*;
+        match self {
+            Html => "html",
+            Head => "head",
+            Style => "style",
+            Link => "link",
+            Script => "script",
+            Meta => "meta",
+            Title => "title",
+            Body => "body",
+            H1 => "h1",
+            H2 => "h2",
+            H3 => "h3",
+            H4 => "h4",
+            H5 => "h5",
+            H6 => "h6",
+            P => "p",
+            Div => "div",
+            Strong => "strong",
+            Em => "em",
+            A => "a",
+            Ul => "ul",
+            Ol => "ol",
+            Li => "li",
+            Pre => "pre",
+            Code => "code",
+            Hr => "hr",
+            Br => "br",
+            Blockquote => "blockquote",
+            Text => "#text",
+            Comment => "#comment",
+            Unknown(tag) | Foreign(_, tag) => tag,
+        }
+    }
+
+    /// Returns the namespace of the element, which is the HTML namespace unless it is a foreign element
+    pub fn namespace(&self) -> Namespace {
+        match self {
+            NodeType::Foreign(namespace, _) => *namespace,
+            _ => Namespace::Html,
+        }
+    }
+
+    /// Returns the qualified name of the element (i.e. `o:p`), or None for texts and comments
+    pub fn qual_name(&self) -> Option<QualName> {
+        if matches!(self, NodeType::Text | NodeType::Comment) {
+            return None;
+        }
+        let (prefix, local) = split_prefix(self.tag_str());
+        Some(QualName::new(
+            prefix.map(String::from),
+            local.to_string(),
+            Some(self.namespace()),
+        ))
+    }
+
+    /// Checks whether the element is an autonomous custom element (i.e. `<my-card>`),
+    /// whose name starts with a lowercase letter and contains a hyphen
+    ///
+    /// See <https://html.spec.whatwg.org/multipage/custom-elements.html#valid-custom-element-name>
+    pub fn is_custom_element(&self) -> bool {
+        let NodeType::Unknown(name) = self else {
+            return false;
+        };
+        name.starts_with(|c: char| c.is_ascii_lowercase())
+            && name.contains('-')
+            && name.chars().all(|c| {
+                matches!(c, 'a'..='z' | '0'..='9' | '-' | '.' | '_' | '\u{B7}')
+                    || (c > '\u{BF}' && c != '\u{D7}' && c != '\u{F7}')
+            })
+            && !matches!(
+                name.as_str(),
+                "annotation-xml"
+                    | "color-profile"
+                    | "font-face"
+                    | "font-face-src"
+                    | "font-face-uri"
+                    | "font-face-format"
+                    | "font-face-name"
+                    | "missing-glyph"
+            )
+    }
+
     pub fn from_tag_str(input: &str) -> Self {
         use NodeType::*;
         match input.to_lowercase().as_str() {
@@ -77,17 +326,29 @@ impl NodeType {
     }
 }
 
-/// Represents a node in the HTML tree.
+/// Represents a node in the HTML tree, whose text values are owned Strings.
+pub type Node = GenericNode<String>;
+
+/// Represents a node in the HTML tree, whose text values borrow from the input HTML where no decoding is needed.
+///
+/// It is returned by `parser::safe_parse_html_borrowed`, and can be converted to a Node with `into_owned`.
+pub type BorrowedNode<'a> = GenericNode<Cow<'a, str>>;
+
+/// Represents a node in the HTML tree, generic over the type of its text value (the `value` of texts and comments).
+///
+/// Use the [Node] and [BorrowedNode] aliases instead of this type directly.
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
-pub struct Node {
+pub struct GenericNode<V> {
     pub tag_name: Option<NodeType>,
-    pub value: Option<String>,
+    pub value: Option<V>,
     pub attributes: Option<Attributes>,
     pub within_special_tag: Option<Vec<NodeType>>,
-    pub children: Vec<Node>,
+    pub children: Vec<GenericNode<V>>,
+    /// The position of the node in the source HTML, which is only recorded when parsing with `ParseConfig::record_spans`
+    pub span: Option<Span>,
 }
 
-impl Node {
+impl<V> GenericNode<V> {
     /// Checks whether the node is within any of the special tags passed in
     pub fn is_in_special_tag(&self, tags: &[NodeType]) -> bool {
         if let Some(within_special_tag) = &self.within_special_tag {
@@ -97,6 +358,18 @@ impl Node {
         }
     }
 
+    /// Returns the namespace of the node, which is the HTML namespace for texts, comments and the root node
+    pub fn namespace(&self) -> Namespace {
+        self.tag_name
+            .as_ref()
+            .map_or(Namespace::Html, NodeType::namespace)
+    }
+
+    /// Returns the qualified name of the element, or None for texts, comments and the root node
+    pub fn qual_name(&self) -> Option<QualName> {
+        self.tag_name.as_ref().and_then(NodeType::qual_name)
+    }
+
     /// Returns the leading spaces if there is any
     /// This is used to format the output of the unordered and ordered lists
     pub fn leading_spaces(&self) -> String {
@@ -115,24 +388,147 @@ impl Node {
         }
     }
 
+    /// Returns the position of the node in the source HTML, same as the `span` field
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// Drops the node from a stack instead of recursion, so that dropping a deeply nested tree can't overflow the stack.
+    ///
+    /// A tree parsed with `Limits::unlimited` can be nested deep enough to overflow the stack when it is dropped as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use html2md_rs::{
+    ///     parser::safe_parse_html_with_config,
+    ///     structs::{Limits, ParseConfig},
+    /// };
+    ///
+    /// let input = "<div>".repeat(100_000);
+    /// let config = ParseConfig {
+    ///     limits: Limits::unlimited(),
+    ///     ..Default::default()
+    /// };
+    /// let parsed = safe_parse_html_with_config(input, &config).unwrap();
+    /// parsed.drop_iteratively();
+    /// ```
+    pub fn drop_iteratively(self) {
+        let mut nodes = vec![self];
+        while let Some(mut node) = nodes.pop() {
+            nodes.append(&mut node.children);
+        }
+    }
+
     /// Creates a new Node from tag_name, value, attributes, within_special_tag and children
     pub fn new(
         tag_name: Option<NodeType>,
-        value: Option<String>,
+        value: Option<V>,
         attributes: Option<Attributes>,
         within_special_tag: Option<Vec<NodeType>>,
-        children: Vec<Node>,
+        children: Vec<GenericNode<V>>,
     ) -> Self {
-        Node {
+        GenericNode {
             tag_name,
             value,
             attributes,
             within_special_tag,
             children,
+            span: None,
         }
     }
 }
 
+impl BorrowedNode<'_> {
+    /// Converts the node into a Node which owns its text values
+    ///
+    /// The nodes are converted from a stack instead of recursion, so that a deeply nested tree can't overflow the stack,
+    /// and the Node can be dropped with `drop_iteratively`.
+    pub fn into_owned(self) -> Node {
+        // the nodes being converted, along with their children left to convert and the children converted so far
+        let (node, children) = self.into_owned_shallow();
+        let mut stack = vec![(node, children.into_iter(), Vec::new())];
+        while let Some((_, children, _)) = stack.last_mut() {
+            if let Some(child) = children.next() {
+                let (node, children) = child.into_owned_shallow();
+                stack.push((node, children.into_iter(), Vec::new()));
+                continue;
+            }
+            let Some((mut node, _, children)) = stack.pop() else {
+                break;
+            };
+            node.children = children;
+            match stack.last_mut() {
+                Some((_, _, siblings)) => siblings.push(node),
+                None => return node,
+            }
+        }
+        unreachable!("the root node is returned once all of its children are converted")
+    }
+
+    /// Converts the node without its children into a Node, returning the children left to convert
+    fn into_owned_shallow(mut self) -> (Node, Vec<Self>) {
+        let node = Node {
+            tag_name: self.tag_name.take(),
+            value: self.value.take().map(Cow::into_owned),
+            attributes: self.attributes.take(),
+            within_special_tag: self.within_special_tag.take(),
+            children: Vec::new(),
+            span: self.span,
+        };
+        (node, std::mem::take(&mut self.children))
+    }
+}
+
+impl<'a> From<BorrowedNode<'a>> for Node {
+    fn from(node: BorrowedNode<'a>) -> Self {
+        node.into_owned()
+    }
+}
+
+/// Represents a position in the source HTML.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, PartialOrd, Ord, Hash)]
+pub struct SourcePosition {
+    /// The byte offset from the start of the source
+    pub offset: usize,
+    /// The line number, starting from 1
+    pub line: usize,
+    /// The column number in characters, starting from 1
+    pub column: usize,
+}
+
+/// Represents the range of a node in the source HTML,
+/// from the start of its opening tag to the end of its closing tag (or where it was closed implicitly).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash)]
+pub struct Span {
+    /// The position of the first character of the node
+    pub start: SourcePosition,
+    /// The position right after the last character of the node
+    pub end: SourcePosition,
+}
+
+impl Span {
+    /// Returns the range of the bytes of the node in the source HTML
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use html2md_rs::{parser::safe_parse_html_with_config, structs::ParseConfig};
+    ///
+    /// let input = "<p>hello <em>world</em></p>".to_string();
+    /// let config = ParseConfig {
+    ///     record_spans: true,
+    ///     ..Default::default()
+    /// };
+    /// let parsed = safe_parse_html_with_config(input.clone(), &config).unwrap();
+    /// let em = &parsed.children[1];
+    /// assert_eq!(&input[em.span().unwrap().range()], "<em>world</em>");
+    /// ```
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.start.offset..self.end.offset
+    }
+}
+
 /// Represents the Attributes of an HTML element.
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct Attributes {
@@ -203,6 +599,30 @@ impl Attributes {
         }
     }
 
+    /// Returns the names of the attributes, in no particular order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        let id = self.id.as_ref().map(|_| "id");
+        let class = self.class.as_ref().map(|_| "class");
+        id.into_iter()
+            .chain(class)
+            .chain(self.attributes.keys().map(String::as_str))
+    }
+
+    /// Returns the qualified names of the attributes (i.e. `xlink:href`), in no particular order
+    pub fn qual_names(&self) -> impl Iterator<Item = QualName> + '_ {
+        self.names().map(QualName::attribute)
+    }
+
+    /// Returns the attribute value of the qualified name passed in
+    pub fn get_qualified(&self, name: &QualName) -> Option<AttributeValues> {
+        self.get(&name.to_string())
+    }
+
+    /// Returns the number of attributes
+    pub fn len(&self) -> usize {
+        usize::from(self.id.is_some()) + usize::from(self.class.is_some()) + self.attributes.len()
+    }
+
     /// Returns whether the element attributes are empty
     pub fn is_empty(&self) -> bool {
         self.id.is_none() && self.class.is_none() && self.attributes.is_empty()
@@ -285,7 +705,268 @@ impl std::fmt::Display for AttributeValues {
     }
 }
 
-#[derive(Debug, Default)]
+/// Represents the DOCTYPE of an HTML document (i.e. `<!DOCTYPE html>`).
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Doctype {
+    /// The lowercased name of the DOCTYPE (i.e. `html`)
+    pub name: Option<String>,
+    /// The public identifier (i.e. `-//W3C//DTD HTML 4.01//EN`)
+    pub public_id: Option<String>,
+    /// The system identifier (i.e. `http://www.w3.org/TR/html4/strict.dtd`)
+    pub system_id: Option<String>,
+    /// Whether the DOCTYPE is malformed, which always puts the document in quirks mode
+    pub force_quirks: bool,
+}
+
+/// Public identifiers that put the document in quirks mode when the DOCTYPE's public identifier starts with them
+const QUIRKY_PUBLIC_PREFIXES: [&str; 55] = [
+    "+//silmaril//dtd html pro v0r11 19970101//",
+    "-//as//dtd html 3.0 aswedit + extensions//",
+    "-//advasoft ltd//dtd html 3.0 aswedit + extensions//",
+    "-//ietf//dtd html 2.0 level 1//",
+    "-//ietf//dtd html 2.0 level 2//",
+    "-//ietf//dtd html 2.0 strict level 1//",
+    "-//ietf//dtd html 2.0 strict level 2//",
+    "-//ietf//dtd html 2.0 strict//",
+    "-//ietf//dtd html 2.0//",
+    "-//ietf//dtd html 2.1e//",
+    "-//ietf//dtd html 3.0//",
+    "-//ietf//dtd html 3.2 final//",
+    "-//ietf//dtd html 3.2//",
+    "-//ietf//dtd html 3//",
+    "-//ietf//dtd html level 0//",
+    "-//ietf//dtd html level 1//",
+    "-//ietf//dtd html level 2//",
+    "-//ietf//dtd html level 3//",
+    "-//ietf//dtd html strict level 0//",
+    "-//ietf//dtd html strict level 1//",
+    "-//ietf//dtd html strict level 2//",
+    "-//ietf//dtd html strict level 3//",
+    "-//ietf//dtd html strict//",
+    "-//ietf//dtd html//",
+    "-//metrius//dtd metrius presentational//",
+    "-//microsoft//dtd internet explorer 2.0 html strict//",
+    "-//microsoft//dtd internet explorer 2.0 html//",
+    "-//microsoft//dtd internet explorer 2.0 tables//",
+    "-//microsoft//dtd internet explorer 3.0 html strict//",
+    "-//microsoft//dtd internet explorer 3.0 html//",
+    "-//microsoft//dtd internet explorer 3.0 tables//",
+    "-//netscape comm. corp.//dtd html//",
+    "-//netscape comm. corp.//dtd strict html//",
+    "-//o'reilly and associates//dtd html 2.0//",
+    "-//o'reilly and associates//dtd html extended 1.0//",
+    "-//o'reilly and associates//dtd html extended relaxed 1.0//",
+    "-//sq//dtd html 2.0 hotmetal + extensions//",
+    "-//softquad software//dtd hotmetal pro 6.0::19990601::extensions to html 4.0//",
+    "-//softquad//dtd hotmetal pro 4.0::19971010::extensions to html 4.0//",
+    "-//spyglass//dtd html 2.0 extended//",
+    "-//sun microsystems corp.//dtd hotjava html//",
+    "-//sun microsystems corp.//dtd hotjava strict html//",
+    "-//w3c//dtd html 3 1995-03-24//",
+    "-//w3c//dtd html 3.2 draft//",
+    "-//w3c//dtd html 3.2 final//",
+    "-//w3c//dtd html 3.2//",
+    "-//w3c//dtd html 3.2s draft//",
+    "-//w3c//dtd html 4.0 frameset//",
+    "-//w3c//dtd html 4.0 transitional//",
+    "-//w3c//dtd html experimental 19960712//",
+    "-//w3c//dtd html experimental 970421//",
+    "-//w3c//dtd w3 html//",
+    "-//w3o//dtd w3 html 3.0//",
+    "-//webtechs//dtd mozilla html 2.0//",
+    "-//webtechs//dtd mozilla html//",
+];
+
+impl Doctype {
+    /// Returns the rendering mode browsers use for a document with this DOCTYPE
+    ///
+    /// See <https://html.spec.whatwg.org/multipage/parsing.html#the-initial-insertion-mode>
+    pub fn quirks_mode(&self) -> QuirksMode {
+        let public_id = self.public_id.as_deref().map(str::to_ascii_lowercase);
+        let system_id = self.system_id.as_deref().map(str::to_ascii_lowercase);
+        let public_id_starts_with = |prefixes: &[&str]| {
+            public_id
+                .as_deref()
+                .is_some_and(|id| prefixes.iter().any(|prefix| id.starts_with(prefix)))
+        };
+        let html4_prefixes = [
+            "-//w3c//dtd html 4.01 frameset//",
+            "-//w3c//dtd html 4.01 transitional//",
+        ];
+
+        if self.force_quirks
+            || self.name.as_deref() != Some("html")
+            || matches!(
+                public_id.as_deref(),
+                Some(
+                    "-//w3o//dtd w3 html strict 3.0//en//"
+                        | "-/w3c/dtd html 4.0 transitional/en"
+                        | "html"
+                )
+            )
+            || system_id.as_deref()
+                == Some("http://www.ibm.com/data/dtd/v11/ibmxhtml1-transitional.dtd")
+            || public_id_starts_with(&QUIRKY_PUBLIC_PREFIXES)
+            || (system_id.is_none() && public_id_starts_with(&html4_prefixes))
+        {
+            return QuirksMode::Quirks;
+        }
+
+        if public_id_starts_with(&[
+            "-//w3c//dtd xhtml 1.0 frameset//",
+            "-//w3c//dtd xhtml 1.0 transitional//",
+        ]) || (system_id.is_some() && public_id_starts_with(&html4_prefixes))
+        {
+            return QuirksMode::LimitedQuirks;
+        }
+
+        QuirksMode::NoQuirks
+    }
+}
+
+/// Represents the rendering modes browsers choose from based on the DOCTYPE of a document.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum QuirksMode {
+    /// The standards mode, i.e. `<!DOCTYPE html>`
+    #[default]
+    NoQuirks,
+    /// The "almost standards" mode, i.e. the XHTML 1.0 Transitional DOCTYPE
+    LimitedQuirks,
+    /// The quirks mode, for documents without or with a legacy DOCTYPE
+    Quirks,
+}
+
+/// Represents a parsed HTML document, which consists of the root Node and the DOCTYPE.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ParsedHtml<V = String> {
+    /// The root of the parsed Node tree
+    pub root: GenericNode<V>,
+    /// The first DOCTYPE in the document, if there is any
+    pub doctype: Option<Doctype>,
+    /// The malformed parts of the document recovered from, which are only collected in the lenient mode
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl<V> ParsedHtml<V> {
+    /// Returns the rendering mode browsers use for the document, which is quirks mode when there is no DOCTYPE
+    pub fn quirks_mode(&self) -> QuirksMode {
+        self.doctype
+            .as_ref()
+            .map_or(QuirksMode::Quirks, Doctype::quirks_mode)
+    }
+}
+
+/// Configuration of how HTML is parsed
+#[derive(Debug, Default, Clone)]
+pub struct ParseConfig {
+    /// Whether to record the position of each node in the source HTML in `Node::span`, which is disabled by default to save memory
+    pub record_spans: bool,
+    /// Whether to recover from malformed HTML the way browsers do instead of returning an error,
+    /// collecting what was malformed in `ParsedHtml::warnings`
+    pub lenient: bool,
+    /// Which value is kept when a tag has the same attribute more than once (i.e. `<p class="a" class="b">`)
+    pub duplicate_attributes: DuplicateAttributePolicy,
+    /// The limits on the input and the parsed tree, which stop the parsing with an error when exceeded,
+    /// or are clamped in the lenient mode, keeping the tree parsed before them
+    pub limits: Limits,
+}
+
+/// Limits on the work done by the parser, which protect a service converting untrusted HTML
+/// from exhausting its memory or stack
+///
+/// The defaults are meant for service use, and are far above what real pages need.
+/// Use `Limits::unlimited` to parse trusted HTML of any size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum size of the input in bytes, which is 16 MiB by default
+    pub max_input_size: usize,
+    /// The maximum number of open elements nested in each other, which is 512 by default
+    pub max_depth: usize,
+    /// The maximum number of nodes, including texts and comments, which is 1,000,000 by default
+    pub max_nodes: usize,
+    /// The maximum number of attributes of an element, which is 256 by default
+    pub max_attributes: usize,
+}
+
+impl Limits {
+    /// Returns the limits which never stop the parsing
+    pub fn unlimited() -> Self {
+        Limits {
+            max_input_size: usize::MAX,
+            max_depth: usize::MAX,
+            max_nodes: usize::MAX,
+            max_attributes: usize::MAX,
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_input_size: 16 * 1024 * 1024,
+            max_depth: 512,
+            max_nodes: 1_000_000,
+            max_attributes: 256,
+        }
+    }
+}
+
+/// How a repeated attribute of a tag is handled
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateAttributePolicy {
+    /// The first value is kept, like browsers do
+    #[default]
+    FirstWins,
+    /// The last value overwrites the earlier ones
+    LastWins,
+    /// The repeated attribute is a malformed attribute, which is an error unless the parser is lenient
+    Error,
+}
+
+/// Configures how a Node is rendered to markdown.
+///
+/// More options can be added in future versions, so it is built from `ToMdConfig::default()` with the `with_*` methods.
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::structs::{NodeType::P, ToMdConfig};
+///
+/// let config = ToMdConfig::default()
+///     .with_ignore_rendering(vec![P])
+///     .with_max_output_length(1024);
+///
+/// assert_eq!(config.ignore_rendering, vec![P]);
+/// assert_eq!(config.max_output_length, 1024);
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
 pub struct ToMdConfig {
+    /// The nodes which are not rendered, along with their children
     pub ignore_rendering: Vec<NodeType>,
+    /// The maximum length of the markdown in bytes, which is 64 MiB by default
+    pub max_output_length: usize,
+}
+
+impl ToMdConfig {
+    /// Sets the nodes which are not rendered, along with their children
+    pub fn with_ignore_rendering(mut self, ignore_rendering: Vec<NodeType>) -> Self {
+        self.ignore_rendering = ignore_rendering;
+        self
+    }
+
+    /// Sets the maximum length of the markdown in bytes
+    pub fn with_max_output_length(mut self, max_output_length: usize) -> Self {
+        self.max_output_length = max_output_length;
+        self
+    }
+}
+
+impl Default for ToMdConfig {
+    fn default() -> Self {
+        ToMdConfig {
+            ignore_rendering: Vec::new(),
+            max_output_length: 64 * 1024 * 1024,
+        }
+    }
 }
@@ -0,0 +1,253 @@
+//! This module contains functions that converts a Node back to an HTML string.
+//!
+//! The HTML is serialized the way browsers serialize `innerHTML`, so that parsing it again with `safe_parse_html` gives the same Node.
+//! Texts and attribute values are escaped (i.e. `<` to `&lt;`), except for the texts of raw text elements such as `<script>`.
+//! Void elements (i.e. `<br>`) have no closing tag, and the attributes are written with `id` and `class` first and the rest sorted by name.
+//! Elements and attributes of a constructed Node whose names can't be written as a name (i.e. `a onclick`) are left out,
+//! keeping the children of the elements, so that they can't inject markup.
+//! See <https://html.spec.whatwg.org/multipage/parsing.html#serialising-html-fragments>
+
+use crate::{
+    parser::ParseHTMLError,
+    structs::{AttributeValues, Attributes, GenericNode, Namespace, NodeType},
+};
+
+/// Converts a Node, or a BorrowedNode, to an HTML string.
+///
+/// The root node without a tag name, which holds the top-level nodes of a parsed document, is not written itself.
+///
+/// # Arguments
+///
+/// * `node` - A Node or a BorrowedNode to be converted to HTML.
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::{parser::safe_parse_html, to_html::to_html};
+///
+/// let node = safe_parse_html("<p class=note>Tom &amp; Jerry<br></p>".to_string()).unwrap();
+///
+/// assert_eq!(to_html(&node), "<p class=\"note\">Tom &amp; Jerry<br></p>");
+/// ```
+pub fn to_html<V: AsRef<str>>(node: &GenericNode<V>) -> String {
+    let mut res = String::new();
+    // after `<plaintext>`, the rest of the input is read as text, so the closing tags are left out
+    let mut in_plaintext = false;
+    // the work is popped in the reverse order it is pushed, so the children of a node are pushed in reverse
+    let mut stack = vec![Work::Node(node)];
+    while let Some(work) = stack.pop() {
+        match work {
+            Work::Node(node) => {
+                in_plaintext |= node
+                    .tag_name
+                    .as_ref()
+                    .is_some_and(|tag| tag.is_unknown("plaintext"));
+                write_node(node, &mut res, &mut stack);
+            }
+            Work::RawText(text) => res.push_str(text),
+            Work::EndTag(name) if !in_plaintext => {
+                res.push_str("</");
+                res.push_str(name);
+                res.push('>');
+            }
+            Work::EndTag(_) => {}
+        }
+    }
+    res
+}
+
+/// Converts the attributes of an element to HTML (i.e. `id="main" checked`), with `id` and `class` first and the rest sorted by name.
+///
+/// Boolean attributes are written by their name alone, and left out when they are false.
+/// Attributes whose names would be read as something else (i.e. `a b` or `a>`) are left out.
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::{
+///     structs::{AttributeValues, Attributes},
+///     to_html::attributes_to_html,
+/// };
+///
+/// let attributes = Attributes::from(vec![
+///     ("title".to_string(), AttributeValues::from("\"quoted\"")),
+///     ("id".to_string(), AttributeValues::from("main")),
+///     ("hidden".to_string(), AttributeValues::from(true)),
+///     ("start".to_string(), AttributeValues::from(3)),
+/// ]);
+///
+/// assert_eq!(
+///     attributes_to_html(&attributes),
+///     "id=\"main\" hidden start=\"3\" title=\"&quot;quoted&quot;\""
+/// );
+/// ```
+pub fn attributes_to_html(attributes: &Attributes) -> String {
+    let mut names = attributes
+        .attributes
+        .keys()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    let id = attributes.id.as_ref().map(|_| "id");
+    let class = attributes.class.as_ref().map(|_| "class");
+
+    let mut res = String::new();
+    for name in id.into_iter().chain(class).chain(names) {
+        let value = match attributes.get(name) {
+            Some(AttributeValues::Bool(false)) | None => continue,
+            Some(_) if !is_attribute_name(name) => continue,
+            Some(value) => value,
+        };
+        if !res.is_empty() {
+            res.push(' ');
+            if name.starts_with('=') {
+                // a name starting with an equal sign, which is only read in the lenient mode (i.e. `<p a =b>`),
+                // would be the value of the attribute before it, so it is separated with a forward slash
+                res.push('/');
+            }
+        }
+        res.push_str(name);
+        match value {
+            AttributeValues::String(value) => {
+                res.push_str("=\"");
+                escape(&value, true, &mut res);
+                res.push('"');
+            }
+            AttributeValues::Number(value) => res.push_str(&format!("=\"{}\"", value)),
+            AttributeValues::Bool(_) => {}
+        }
+    }
+    res
+}
+
+/// A part of the HTML waiting to be written in the work stack of `to_html`
+enum Work<'n, V> {
+    /// a node, whose HTML is followed by the HTML of its children
+    Node(&'n GenericNode<V>),
+    /// a text inside a raw text element (i.e. `<script>`), which is written as it is
+    RawText(&'n str),
+    /// the closing tag of an element (i.e. `</p>`), written after its children
+    EndTag(&'n str),
+}
+
+/// Writes the HTML of the node before its children to `res`,
+/// and pushes its children along with its closing tag to the work stack
+fn write_node<'n, V: AsRef<str>>(
+    node: &'n GenericNode<V>,
+    res: &mut String,
+    stack: &mut Vec<Work<'n, V>>,
+) {
+    let value = node.value.as_ref().map_or("", AsRef::as_ref);
+    let tag_name = match &node.tag_name {
+        Some(NodeType::Text) => return escape(value, false, res),
+        Some(NodeType::Comment) => {
+            res.push_str("<!--");
+            res.push_str(value);
+            res.push_str("-->");
+            return;
+        }
+        Some(tag_name) => tag_name,
+        // the root node only holds the top-level nodes
+        None => {
+            stack.extend(node.children.iter().rev().map(Work::Node));
+            return;
+        }
+    };
+
+    let name = tag_name.tag_str();
+    if !is_tag_name(name) {
+        // the element can't be written, so only its children are
+        stack.extend(node.children.iter().rev().map(Work::Node));
+        return;
+    }
+    res.push('<');
+    res.push_str(name);
+    if let Some(attributes) = &node.attributes {
+        let attributes = attributes_to_html(attributes);
+        if !attributes.is_empty() {
+            res.push(' ');
+            res.push_str(&attributes);
+        }
+    }
+    res.push('>');
+
+    if tag_name.namespace() == Namespace::Html && tag_name.is_void_element() {
+        return;
+    }
+    stack.push(Work::EndTag(name));
+    let raw_text = is_raw_text_element(tag_name);
+    for child in node.children.iter().rev() {
+        stack.push(match (&child.tag_name, &child.value) {
+            (Some(NodeType::Text), Some(value)) if raw_text => Work::RawText(value.as_ref()),
+            _ => Work::Node(child),
+        });
+    }
+}
+
+/// Checks whether the name is read back as the name of a tag, which runs until a whitespace, `/` or `>`
+/// and doesn't start a comment or a declaration (i.e. `<!--`, `<?xml`)
+fn is_tag_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with(['!', '?']) && !name.contains(ends_name)
+}
+
+/// Checks whether the name is read back as the name of an attribute, which runs until a whitespace, `/`, `>` or `=`
+/// (except for an `=` at its start)
+fn is_attribute_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(ends_name) && !name.chars().skip(1).any(|char| char == '=')
+}
+
+/// Checks whether the character ends a tag or attribute name
+fn ends_name(char: char) -> bool {
+    char.is_whitespace() || char == '/' || char == '>'
+}
+
+/// Checks whether the texts of the element are written without escaping, since the parser reads them as they are
+fn is_raw_text_element(node_type: &NodeType) -> bool {
+    match node_type {
+        NodeType::Script | NodeType::Style => true,
+        NodeType::Unknown(tag) => matches!(
+            tag.as_str(),
+            "iframe" | "noembed" | "noframes" | "plaintext" | "xmp"
+        ),
+        _ => false,
+    }
+}
+
+/// Writes the text to `res`, escaping `&`, the non-breaking space and `<` and `>`, along with `"` in attribute values
+fn escape(text: &str, in_attribute: bool, res: &mut String) {
+    for char in text.chars() {
+        match char {
+            '&' => res.push_str("&amp;"),
+            '\u{A0}' => res.push_str("&nbsp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' if in_attribute => res.push_str("&quot;"),
+            _ => res.push(char),
+        }
+    }
+}
+
+/// Safely converts a string of HTML to a normalized HTML string, with the tags closed and the attributes quoted.
+///
+/// Returns an error if the HTML is invalid.
+///
+/// # Arguments
+///
+/// * `input` - A string of HTML to be normalized.
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::to_html::safe_from_html_to_html;
+///
+/// let input = "<ul><li>one<li>two</ul>".to_string();
+/// let normalized = safe_from_html_to_html(input);
+///
+/// assert_eq!(
+///     normalized,
+///     Ok("<ul><li>one</li><li>two</li></ul>".to_string())
+/// );
+/// ```
+pub fn safe_from_html_to_html(input: String) -> Result<String, ParseHTMLError> {
+    crate::parser::safe_parse_html_borrowed(&input).map(|html| to_html(&html))
+}
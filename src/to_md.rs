@@ -1,15 +1,16 @@
 //! This module contains functions that converts a Node to a markdown string.
 
 use crate::{
+    entities::starts_with_reference,
     parser::ParseHTMLError,
-    structs::{AttributeValues, Node, NodeType::*, ToMdConfig},
+    structs::{AttributeValues, GenericNode, Namespace, NodeType::*, ToMdConfig},
 };
 
-/// Converts a Node to a markdown string.
+/// Converts a Node, or a BorrowedNode, to a markdown string.
 ///
 /// # Arguments
 ///
-/// * `node` - A Node to be converted to markdown.
+/// * `node` - A Node or a BorrowedNode to be converted to markdown.
 ///
 /// # Examples
 ///
@@ -33,21 +34,25 @@ use crate::{
 ///         attributes: None,
 ///         within_special_tag: None,
 ///         children: Vec::new(),
+///         span: None,
 ///     }],
+///     span: None,
 /// };
 /// let parsed = to_md(input);
 ///
 /// assert_eq!(parsed, "# Hello world\n");
 /// ```
-pub fn to_md(node: Node) -> String {
+pub fn to_md<V: AsRef<str>>(node: GenericNode<V>) -> String {
     to_md_with_config(node, &ToMdConfig::default())
 }
 
 /// Converts a Node to a markdown string with custom config.
 ///
+/// The markdown is cut off at `ToMdConfig::max_output_length`, which `safe_to_md_with_config` returns as an error instead.
+///
 /// # Arguments
 ///
-/// * `node` - A `Node` to be converted to markdown.
+/// * `node` - A `Node` or a `BorrowedNode` to be converted to markdown.
 /// * `config` - A custom configuration, `ToMdConfig`, to use to configure how to render the output markdown.
 ///
 /// # Example's
@@ -85,18 +90,167 @@ pub fn to_md(node: Node) -> String {
 ///     ],
 ///     ..Default::default()
 /// };
-/// let config = ToMdConfig {
-///     ignore_rendering: vec![P],
-/// };
+/// let config = ToMdConfig::default().with_ignore_rendering(vec![P]);
 /// let parsed = to_md_with_config(input, &config);
 ///
 /// assert_eq!(parsed, "# Hello world\n");
 /// ```
-pub fn to_md_with_config(node: Node, config: &ToMdConfig) -> String {
+pub fn to_md_with_config<V: AsRef<str>>(node: GenericNode<V>, config: &ToMdConfig) -> String {
+    let mut res = render(&node, config);
+    node.drop_iteratively();
+    if res.len() > config.max_output_length {
+        let mut end = config.max_output_length;
+        while !res.is_char_boundary(end) {
+            end -= 1;
+        }
+        res.truncate(end);
+    }
+    res
+}
+
+/// Errors that will be returned when the markdown is longer than `ToMdConfig::max_output_length`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct OutputTooLongError {
+    /// The limit that was exceeded
+    pub max_output_length: usize,
+}
+
+impl std::fmt::Display for OutputTooLongError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Markdown longer than the limit of {} bytes",
+            self.max_output_length
+        )
+    }
+}
+
+impl std::error::Error for OutputTooLongError {}
+
+/// Errors that will be returned when converting a string of HTML to markdown
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ToMdError {
+    /// The HTML is malformed or exceeds a limit of the parser
+    Parse(ParseHTMLError),
+    /// The markdown is longer than `ToMdConfig::max_output_length`
+    OutputTooLong(OutputTooLongError),
+}
+
+impl std::fmt::Display for ToMdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToMdError::Parse(error) => error.fmt(f),
+            ToMdError::OutputTooLong(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ToMdError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ToMdError::Parse(error) => Some(error),
+            ToMdError::OutputTooLong(error) => Some(error),
+        }
+    }
+}
+
+impl From<ParseHTMLError> for ToMdError {
+    fn from(error: ParseHTMLError) -> Self {
+        ToMdError::Parse(error)
+    }
+}
+
+impl From<OutputTooLongError> for ToMdError {
+    fn from(error: OutputTooLongError) -> Self {
+        ToMdError::OutputTooLong(error)
+    }
+}
+
+/// Converts a Node to a markdown string with custom config, returning an error instead of a cut off markdown
+/// if it is longer than `ToMdConfig::max_output_length`
+///
+/// # Arguments
+///
+/// * `node` - A Node or a BorrowedNode to be converted to markdown.
+/// * `config` - Custom configuration `ToMdConfig`
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::{
+///     parser::safe_parse_html,
+///     structs::ToMdConfig,
+///     to_md::{safe_to_md_with_config, OutputTooLongError},
+/// };
+///
+/// let node = safe_parse_html("<h1>Hello world</h1>".to_string()).unwrap();
+/// let config = ToMdConfig::default().with_max_output_length(8);
+///
+/// assert_eq!(
+///     safe_to_md_with_config(node, &config),
+///     Err(OutputTooLongError { max_output_length: 8 })
+/// );
+/// ```
+pub fn safe_to_md_with_config<V: AsRef<str>>(
+    node: GenericNode<V>,
+    config: &ToMdConfig,
+) -> Result<String, OutputTooLongError> {
+    let res = render(&node, config);
+    node.drop_iteratively();
+    if res.len() > config.max_output_length {
+        return Err(OutputTooLongError {
+            max_output_length: config.max_output_length,
+        });
+    }
+    Ok(res)
+}
+
+/// A part of the markdown waiting to be rendered in the work stack of `render`
+enum Work<'n, V> {
+    /// a node, whose markdown is followed by the markdown of its children
+    Node(&'n GenericNode<V>),
+    /// a markdown string, written after the children of a node (i.e. `**`) or before a list item (i.e. `- `)
+    Markdown(String),
+    /// the end of a `<code>` or `<pre>`, whose texts are written without escaping
+    EndLiteral,
+}
+
+/// Renders a Node to markdown, stopping early once the markdown is longer than `ToMdConfig::max_output_length`
+///
+/// The nodes are rendered from a work stack instead of recursion, so that a deeply nested tree can't overflow the stack.
+fn render<V: AsRef<str>>(node: &GenericNode<V>, config: &ToMdConfig) -> String {
     let mut res = String::new();
+    // the work is popped in the reverse order it is pushed, so the children of a node are pushed in reverse
+    let mut stack = vec![Work::Node(node)];
+    // the number of `<code>` and `<pre>` the nodes being rendered are in
+    let mut literal = 0;
+    while let Some(work) = stack.pop() {
+        match work {
+            Work::Node(node) => render_node(node, config, &mut res, &mut stack, &mut literal),
+            Work::Markdown(markdown) => res.push_str(&markdown),
+            Work::EndLiteral => literal -= 1,
+        }
+        if res.len() > config.max_output_length {
+            break;
+        }
+    }
+    res
+}
+
+/// Writes the markdown of the node before its children to `res`,
+/// and pushes its children along with the markdown after them to the work stack
+fn render_node<'n, V: AsRef<str>>(
+    node: &'n GenericNode<V>,
+    config: &ToMdConfig,
+    res: &mut String,
+    stack: &mut Vec<Work<'n, V>>,
+    literal: &mut usize,
+) {
     let mut tail = String::new();
+    // the markers of the list items (i.e. `- `), written before each child of a list
+    let mut markers: Option<Vec<String>> = None;
 
-    let mut follow_child = true; // If the function should process the children of the node, defaults to true. False for some tags; like <ul> and <ol>.
+    let mut follow_child = true; // If the function should process the children of the node, defaults to true. False for some tags; like <hr> and <br>.
 
     if let Some(tag_type) = &node.tag_name {
         if config.ignore_rendering.contains(tag_type) {
@@ -143,31 +297,32 @@ pub fn to_md_with_config(node: Node, config: &ToMdConfig) -> String {
                     }
                 }
                 Ul => {
-                    for child in &node.children {
-                        res.push_str(&child.leading_spaces());
-                        res.push_str("- ");
-                        res.push_str(&to_md(child.clone()));
-                    }
-                    follow_child = false;
+                    markers = Some(
+                        node.children
+                            .iter()
+                            .map(|child| format!("{}- ", child.leading_spaces()))
+                            .collect(),
+                    );
                 }
                 Ol => {
-                    let mut i = node
+                    let start = node
                         .attributes
                         .as_ref()
                         .and_then(|attrs| attrs.get("start"))
                         .and_then(|start| match start {
-                            AttributeValues::String(start) => start.parse::<usize>().ok(),
-                            AttributeValues::Number(start) => Some(start as usize),
+                            // a negative start can't be written in markdown
+                            AttributeValues::Number(start) => usize::try_from(start).ok(),
+                            // a hand-built node can hold the start as a string
+                            AttributeValues::String(start) => start.trim().parse::<usize>().ok(),
                             _ => None,
                         })
                         .unwrap_or(1);
-                    for child in &node.children {
-                        res.push_str(&child.leading_spaces());
-                        res.push_str(&format!("{}. ", i));
-                        res.push_str(&to_md(child.clone()));
-                        i += 1;
-                    }
-                    follow_child = false;
+                    markers = Some(
+                        (start..)
+                            .zip(&node.children)
+                            .map(|(i, child)| format!("{}{}. ", child.leading_spaces(), i))
+                            .collect(),
+                    );
                 }
                 Li => {
                     if !&node.children.iter().any(|child| child.tag_name == Some(P)) {
@@ -176,11 +331,13 @@ pub fn to_md_with_config(node: Node, config: &ToMdConfig) -> String {
                 }
                 P => {
                     if node.children.is_empty() {
-                        return res;
+                        return;
                     }
                     tail.push('\n');
                 }
                 Code => {
+                    *literal += 1;
+                    stack.push(Work::EndLiteral);
                     if let Some(language) = node
                         .attributes
                         .as_ref()
@@ -210,16 +367,38 @@ pub fn to_md_with_config(node: Node, config: &ToMdConfig) -> String {
                             res.push_str("> ");
                         }
                     }
-                    res.push_str(&node.value.unwrap_or("".to_string()));
-                    return res;
+                    let text = node.value.as_ref().map_or("", AsRef::as_ref);
+                    if *literal > 0 {
+                        res.push_str(text);
+                    } else {
+                        escape_text(text, res);
+                    }
+                    return;
+                }
+                Pre => {
+                    *literal += 1;
+                    stack.push(Work::EndLiteral);
                 }
-                Html | Head | Style | Link | Script | Meta | Body | Div | Pre | Blockquote => (),
+                Html | Head | Style | Link | Script | Meta | Body | Div | Blockquote => (),
                 Title => {
                     follow_child = false;
                 }
                 Comment => {
-                    res.push_str(&format!("<!--{}-->", &node.value.unwrap_or("".to_string())));
-                    return res;
+                    res.push_str(&format!(
+                        "<!--{}-->",
+                        node.value.as_ref().map_or("", AsRef::as_ref)
+                    ));
+                    return;
+                }
+                // an image drawn with SVG has no text to show
+                Foreign(Namespace::Svg, _) => {
+                    follow_child = false;
+                }
+                // only the text of a formula is kept, without its alternative representations
+                Foreign(_, tag) => {
+                    if matches!(tag.as_str(), "annotation" | "annotation-xml") {
+                        follow_child = false;
+                    }
                 }
                 Unknown(tag) => {
                     res.push_str(&format!("<{}>", tag));
@@ -229,15 +408,75 @@ pub fn to_md_with_config(node: Node, config: &ToMdConfig) -> String {
         }
     }
 
+    if !tail.is_empty() {
+        stack.push(Work::Markdown(tail));
+    }
     if follow_child {
-        for child in node.children {
-            res.push_str(&to_md_with_config(child, config));
+        for (index, child) in node.children.iter().enumerate().rev() {
+            stack.push(Work::Node(child));
+            if let Some(markers) = &mut markers {
+                stack.push(Work::Markdown(std::mem::take(&mut markers[index])));
+            }
         }
     }
+}
 
-    res.push_str(&tail);
+/// Writes the text to `res` so that it is shown as it is rather than read as markdown or HTML
+///
+/// `&` is written as `&amp;` when a character reference follows it (i.e. `&amp;lt;`), and `<` as `&lt;` when it could start a tag or an autolink,
+/// so that a decoded `&lt;img&gt;` doesn't become a live HTML tag. Any other `&`, `<` and `>` is written as it is.
+/// The markdown punctuation is escaped with a backslash (i.e. `\*`), along with `#`, `+`, `-`, `=` and `>` at the start of a line.
+fn escape_text(text: &str, res: &mut String) {
+    for (index, char) in text.char_indices() {
+        let at_line_start = res.is_empty() || res.ends_with('\n') || res.ends_with("> ");
+        let rest = &text[index + char.len_utf8()..];
+        match char {
+            '&' if starts_with_reference(rest) => res.push_str("&amp;"),
+            '<' if starts_markup(rest) => res.push_str("&lt;"),
+            '\\' | '*' | '_' | '`' | '[' | ']' => {
+                res.push('\\');
+                res.push(char);
+            }
+            '#' | '+' | '-' | '=' | '>' if at_line_start => {
+                res.push('\\');
+                res.push(char);
+            }
+            _ => res.push(char),
+        }
+    }
+}
 
-    res
+/// Checks whether the text right after a `<` could be read as HTML or an autolink
+///
+/// Closing tags, comments, declarations and tags with attributes (or an autolink such as `<javascript:x>`) are markup,
+/// while a tag name followed by `>` alone (i.e. `<show>`) is only markup when the element changes how the text after it is read.
+fn starts_markup(rest: &str) -> bool {
+    if rest.starts_with(['/', '!', '?']) {
+        return true;
+    }
+    if !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return false;
+    }
+    let name_len = rest
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+        .unwrap_or(rest.len());
+    !rest[name_len..].starts_with('>')
+        || matches!(
+            rest[..name_len].to_ascii_lowercase().as_str(),
+            "iframe"
+                | "math"
+                | "noembed"
+                | "noframes"
+                | "noscript"
+                | "plaintext"
+                | "script"
+                | "style"
+                | "svg"
+                | "template"
+                | "textarea"
+                | "title"
+                | "xmp"
+        )
 }
 
 // https://github.com/izyuumi/html2md-rs/issues/34
@@ -281,7 +520,9 @@ pub fn from_html_to_md(input: String) -> String {
 
 /// Safely converts a string of HTML to a markdown string.
 ///
-/// Returns an error if the HTML is invalid.
+/// Returns an error if the HTML is invalid. It never panics, whatever the input is.
+///
+/// The markdown is cut off at the default `ToMdConfig::max_output_length`, which `safe_from_html_to_md_checked` returns as an error instead.
 ///
 /// # Arguments
 ///
@@ -298,12 +539,14 @@ pub fn from_html_to_md(input: String) -> String {
 /// assert_eq!(parsed, Ok("# Hello world\n".to_string()));
 /// ```
 pub fn safe_from_html_to_md(input: String) -> Result<String, ParseHTMLError> {
-    crate::parser::safe_parse_html(input).map(to_md)
+    safe_from_html_to_md_with_config(input, &ToMdConfig::default())
 }
 
 /// Safely converts a string of HTML to a markdown string with custom config.
 ///
-/// Returns an error if the HTML is invalid.
+/// Returns an error if the HTML is invalid or exceeds the default `Limits` of the parser.
+///
+/// The markdown is cut off at `ToMdConfig::max_output_length`, which `safe_from_html_to_md_checked` returns as an error instead.
 ///
 /// # Arguments
 ///
@@ -319,9 +562,7 @@ pub fn safe_from_html_to_md(input: String) -> Result<String, ParseHTMLError> {
 /// };
 ///
 /// let input = "<h1>Hello world</h1><p>this will not be rendered</p>".to_string();
-/// let config = ToMdConfig {
-///     ignore_rendering: vec![P],
-/// };
+/// let config = ToMdConfig::default().with_ignore_rendering(vec![P]);
 /// let parsed = safe_from_html_to_md_with_config(input, &config);
 ///
 /// assert_eq!(parsed, Ok("# Hello world\n".to_string()));
@@ -330,5 +571,43 @@ pub fn safe_from_html_to_md_with_config(
     input: String,
     config: &ToMdConfig,
 ) -> Result<String, ParseHTMLError> {
-    crate::parser::safe_parse_html(input).map(|html| to_md_with_config(html, config))
+    crate::parser::safe_parse_html_borrowed(&input).map(|html| to_md_with_config(html, config))
+}
+
+/// Safely converts a string of HTML to a markdown string with custom config,
+/// returning an error instead of a cut off markdown if it is longer than `ToMdConfig::max_output_length`.
+///
+/// Returns an error if the HTML is invalid or exceeds the default `Limits` of the parser,
+/// or if the markdown is longer than `ToMdConfig::max_output_length`.
+///
+/// # Arguments
+///
+/// * `input` - A string of HTML to be converted to markdown.
+/// * `config` - Custom configuration `ToMdConfig`
+///
+/// # Examples
+///
+/// ```
+/// use html2md_rs::{
+///     structs::ToMdConfig,
+///     to_md::{safe_from_html_to_md_checked, OutputTooLongError, ToMdError},
+/// };
+///
+/// let input = "<h1>Hello world</h1>".to_string();
+/// let config = ToMdConfig::default().with_max_output_length(8);
+/// let parsed = safe_from_html_to_md_checked(input, &config);
+///
+/// assert_eq!(
+///     parsed,
+///     Err(ToMdError::OutputTooLong(OutputTooLongError {
+///         max_output_length: 8
+///     }))
+/// );
+/// ```
+pub fn safe_from_html_to_md_checked(
+    input: String,
+    config: &ToMdConfig,
+) -> Result<String, ToMdError> {
+    let html = crate::parser::safe_parse_html_borrowed(&input)?;
+    Ok(safe_to_md_with_config(html, config)?)
 }
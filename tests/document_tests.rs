@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod document_tests {
+    use html2md_rs::{
+        document::{Document, DocumentNode},
+        parser::{safe_parse_html, safe_parse_html_with_config},
+        structs::{Node, NodeType::*, ParseConfig},
+        to_md::to_md,
+    };
+
+    #[test]
+    fn navigation() {
+        let input = "<div><h1>title</h1><p>one</p><p>two</p></div>".to_string();
+        let document = Document::from(safe_parse_html(input).unwrap());
+
+        let div = document.root();
+        assert_eq!(document[div].tag_name, Some(Div));
+        assert_eq!(document.parent(div), None);
+
+        let children = document.children(div).collect::<Vec<_>>();
+        assert_eq!(children.len(), 3);
+        let [h1, first_p, second_p] = children[..] else {
+            panic!("expected 3 children")
+        };
+        assert_eq!(document[h1].tag_name, Some(H1));
+        assert_eq!(document.previous_sibling(h1), None);
+        assert_eq!(document.next_sibling(h1), Some(first_p));
+        assert_eq!(document.previous_sibling(second_p), Some(first_p));
+        assert_eq!(document.next_sibling(second_p), None);
+        assert_eq!(document[div].first_child(), Some(h1));
+        assert_eq!(document[div].last_child(), Some(second_p));
+
+        let text = document[second_p].first_child().unwrap();
+        assert_eq!(document[text].value, Some("two".to_string()));
+        assert_eq!(document.parent(text), Some(second_p));
+        assert_eq!(
+            document.ancestors(text).collect::<Vec<_>>(),
+            vec![second_p, div]
+        );
+
+        let texts = document
+            .descendants(div)
+            .filter_map(|id| document[id].value.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec!["title", "one", "two"]);
+        assert_eq!(document.descendants(first_p).count(), 2);
+        assert_eq!(document.len(), 7);
+    }
+
+    #[test]
+    fn special_tags_from_ancestors() {
+        let input =
+            "<blockquote><ul><li>one<ol><li>two</li></ol></li></ul></blockquote><p>three</p>"
+                .to_string();
+        let document = Document::from(safe_parse_html(input).unwrap());
+
+        let text = |value: &str| {
+            document
+                .descendants(document.root())
+                .find(|&id| document[id].value.as_deref() == Some(value))
+                .unwrap()
+        };
+        assert!(document.is_within(text("one"), &[Blockquote]));
+        assert!(!document.is_within(text("one"), &[Ol]));
+        assert!(document.is_within(text("two"), &[Ol]));
+        assert!(!document.is_within(text("three"), &[Blockquote, Ul, Ol]));
+    }
+
+    #[test]
+    fn node_round_trip() {
+        let inputs = [
+            "<div><p>hello <strong>world</strong></p><!-- note --></div>",
+            "<blockquote><p>hello</p><p>world</p></blockquote>",
+            "<ul><li><p>abc</p><ul><li><p>abc</p><ol><li>123</li></ol></li></ul></li></ul>",
+            "<p>one</p><p>two</p>",
+            "",
+        ];
+        let config = ParseConfig {
+            record_spans: true,
+            ..Default::default()
+        };
+        for input in inputs {
+            let node = safe_parse_html_with_config(input.to_string(), &config).unwrap();
+            let document = Document::from(node.clone());
+            assert_eq!(Node::from(document.clone()), node, "input: {:?}", input);
+
+            // a subtree converts the same as the node of the parsed tree
+            if let Some(first_child) = document[document.root()].first_child() {
+                assert_eq!(document.to_node(first_child), node.children[0]);
+            }
+        }
+    }
+
+    #[test]
+    fn build_document() {
+        let mut document = Document::with_root(DocumentNode::new(Some(Ul), None, None));
+        let root = document.root();
+        for value in ["hello", "world"] {
+            let li = document.append(root, DocumentNode::new(Some(Li), None, None));
+            document.append(
+                li,
+                DocumentNode::new(Some(Text), Some(value.to_string()), None),
+            );
+        }
+        let p = document.append_node(
+            root,
+            safe_parse_html("<p>from <em>a node</em></p>".to_string()).unwrap(),
+        );
+        assert_eq!(document[p].tag_name, Some(P));
+        assert_eq!(document.next_sibling(p), None);
+        assert_eq!(document.descendants(p).count(), 4);
+
+        let node = Node::from(document);
+        assert_eq!(
+            node.children[0].children[0].within_special_tag,
+            Some(vec![Ul])
+        );
+        assert_eq!(to_md(node), "- hello\n- world\n- from *a node*\n");
+    }
+}
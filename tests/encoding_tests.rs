@@ -0,0 +1,173 @@
+#[cfg(test)]
+mod encoding_tests {
+    use html2md_rs::{
+        encoding::{decode_html, sniff_encoding, Encoding},
+        parser::{safe_parse_html, safe_parse_html_bytes},
+        to_md::to_md,
+    };
+    use std::borrow::Cow;
+
+    #[test]
+    fn byte_order_marks() {
+        assert_eq!(
+            sniff_encoding(b"\xEF\xBB\xBF<p>hi</p>"),
+            (Encoding::Utf8, 3)
+        );
+        assert_eq!(
+            sniff_encoding(b"\xFF\xFE<\x00p\x00"),
+            (Encoding::Utf16Le, 2)
+        );
+        assert_eq!(
+            sniff_encoding(b"\xFE\xFF\x00<\x00p"),
+            (Encoding::Utf16Be, 2)
+        );
+
+        // the byte order mark wins over the declared encoding
+        let input = b"\xEF\xBB\xBF<meta charset=\"windows-1251\"><p>\xC3\xA9</p>";
+        let (decoded, encoding) = decode_html(input);
+        assert_eq!(encoding, Encoding::Utf8);
+        assert_eq!(decoded, "<meta charset=\"windows-1251\"><p>\u{e9}</p>");
+
+        let input = "\u{FEFF}<p>\u{1F600} hi</p>"
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect::<Vec<_>>();
+        assert_eq!(decode_html(&input).0, "<p>\u{1F600} hi</p>");
+
+        // an unpaired surrogate and an odd trailing byte are replaced
+        assert_eq!(
+            Encoding::Utf16Le.decode(b"a\x00\x00\xD8b\x00c"),
+            "a\u{FFFD}b\u{FFFD}"
+        );
+    }
+
+    #[test]
+    fn meta_prescan() {
+        let cases: [(&[u8], Encoding); 10] = [
+            (b"<meta charset=windows-1251>", Encoding::Windows1251),
+            (b"<META CHARSET='Latin1'/>", Encoding::Windows1252),
+            (
+                b"<meta http-equiv=\"Content-Type\" content=\"text/html; charset=windows-1250\">",
+                Encoding::Windows1250,
+            ),
+            (
+                b"<meta content='text/html;charset=\"cp1253\"' http-equiv=content-type>",
+                Encoding::Windows1253,
+            ),
+            // the content attribute is only used along with http-equiv
+            (
+                b"<meta content=\"text/html; charset=windows-1250\">\xE9",
+                Encoding::Windows1252,
+            ),
+            // the first charset attribute of the tag is used
+            (
+                b"<meta charset=\"utf-8\" charset=\"cp1251\">",
+                Encoding::Utf8,
+            ),
+            // a document can't declare UTF-16, since the declaration was read as ASCII
+            (b"<meta charset=\"utf-16le\">", Encoding::Utf8),
+            (b"<meta charset=\"x-user-defined\">", Encoding::Windows1252),
+            // an unsupported encoding falls back to UTF-8 for valid UTF-8
+            (b"<meta charset=\"shift_jis\"><p>hi</p>", Encoding::Utf8),
+            (
+                b"<head><title>x</title><meta name=viewport charset=tis-620>",
+                Encoding::Windows874,
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                sniff_encoding(input),
+                (expected, 0),
+                "input: {:?}",
+                String::from_utf8_lossy(input)
+            );
+        }
+    }
+
+    #[test]
+    fn meta_prescan_skips_comments_and_attributes() {
+        let cases: [&[u8]; 5] = [
+            b"<!-- <meta charset=\"windows-1251\"> -->\xE9",
+            b"<div title=\"<meta charset=windows-1251>\">\xE9",
+            b"<!--><meta charset=\"windows-1250\">\xE9",
+            b"<meta charset=\"windows-1251\"",
+            b"<metal charset=\"windows-1251\">\xE9",
+        ];
+        assert_eq!(sniff_encoding(cases[0]), (Encoding::Windows1252, 0));
+        assert_eq!(sniff_encoding(cases[1]), (Encoding::Windows1252, 0));
+        // `<!-->` is a complete comment
+        assert_eq!(sniff_encoding(cases[2]), (Encoding::Windows1250, 0));
+        // a tag cut off by the end is ignored
+        assert_eq!(sniff_encoding(cases[3]), (Encoding::Utf8, 0));
+        assert_eq!(sniff_encoding(cases[4]), (Encoding::Windows1252, 0));
+
+        // only the first 1024 bytes are scanned
+        let mut input = b"<p>".to_vec();
+        input.extend(std::iter::repeat_n(b'a', 1024));
+        input.extend_from_slice(b"<meta charset=\"windows-1251\">");
+        assert_eq!(sniff_encoding(&input), (Encoding::Utf8, 0));
+    }
+
+    #[test]
+    fn single_byte_code_pages() {
+        let cases = [
+            (
+                Encoding::Windows874,
+                b"\xA1\xDB\x85".as_slice(),
+                "\u{0E01}\u{FFFD}\u{2026}",
+            ),
+            (
+                Encoding::Windows1250,
+                b"\x8A\xB9\x81",
+                "\u{0160}\u{0105}\u{0081}",
+            ),
+            (Encoding::Windows1251, b"\xCF\xF0\xE8\xE2\xE5\xF2", "Привет"),
+            (
+                Encoding::Windows1252,
+                b"\x93hi\x94 \x80\x8D",
+                "\u{201C}hi\u{201D} \u{20AC}\u{008D}",
+            ),
+            (Encoding::Windows1253, b"\xC1\xAA", "\u{0391}\u{FFFD}"),
+            (Encoding::Windows1254, b"\xD0\xFD", "\u{011E}\u{0131}"),
+            (Encoding::Windows1255, b"\xE0\xCA", "\u{05D0}\u{05BA}"),
+            (Encoding::Windows1256, b"\xC7\x81", "\u{0627}\u{067E}"),
+            (Encoding::Windows1257, b"\xE0\xA1", "\u{0105}\u{FFFD}"),
+            (Encoding::Windows1258, b"\xCC\xFE", "\u{0300}\u{20AB}"),
+        ];
+        for (encoding, input, expected) in cases {
+            assert_eq!(encoding.decode(input), expected, "{}", encoding.name());
+            assert_eq!(Encoding::for_label(encoding.name()), Some(encoding));
+        }
+
+        // ASCII is borrowed without decoding
+        assert!(matches!(
+            Encoding::Windows1251.decode(b"<p>hi</p>"),
+            Cow::Borrowed("<p>hi</p>")
+        ));
+    }
+
+    #[test]
+    fn parse_bytes() {
+        let input = b"<html><head><meta http-equiv=\"content-type\" content=\"text/html; charset=windows-1251\"></head><body><h1>\xCF\xF0\xE8\xE2\xE5\xF2</h1></body></html>";
+        let (parsed, encoding) = safe_parse_html_bytes(input).unwrap();
+        assert_eq!(to_md(parsed), "# Привет\n");
+        assert_eq!(encoding, Encoding::Windows1251);
+
+        let input = "<p>caf\u{e9}</p>";
+        let expected = safe_parse_html(input.to_string()).unwrap();
+        assert_eq!(
+            safe_parse_html_bytes(input.as_bytes()).unwrap(),
+            (expected.clone(), Encoding::Utf8)
+        );
+        assert_eq!(
+            safe_parse_html_bytes(b"<p>caf\xE9</p>").unwrap(),
+            (expected, Encoding::Windows1252)
+        );
+
+        // an unsupported charset falls back like an undeclared one, which is returned to tell the bytes were not decoded as declared
+        let input = b"<meta charset=shift_jis><p>\x93\xfa\x96\x7b</p>";
+        let (parsed, encoding) = safe_parse_html_bytes(input).unwrap();
+        assert_eq!(encoding, Encoding::Windows1252);
+        assert_eq!(to_md(parsed), "\u{201c}\u{fa}\u{2013}{\n");
+    }
+}
@@ -1,9 +1,20 @@
 #[cfg(test)]
 mod parser_tests {
     use html2md_rs::{
-        parser::{safe_parse_html, MalformedTagError, ParseHTMLError},
-        structs::{AttributeValues, Attributes, Node, NodeType::*},
+        parser::{
+            parse_html_lenient, safe_parse_html, safe_parse_html_borrowed,
+            safe_parse_html_borrowed_with_config, safe_parse_html_document,
+            safe_parse_html_document_with_config, safe_parse_html_fragment,
+            safe_parse_html_fragment_with_config, safe_parse_html_reader,
+            safe_parse_html_with_config, LimitExceededError, MalformedAttributeError,
+            MalformedTagError, ParseHTMLError, ParseWarningKind, StreamingParser,
+        },
+        structs::{
+            AttributeValues, Attributes, Doctype, DuplicateAttributePolicy, Limits, Namespace,
+            Node, NodeType, NodeType::*, ParseConfig, QualName, QuirksMode, SourcePosition,
+        },
     };
+    use std::borrow::Cow;
 
     #[test]
     fn parse_simple_div_with_text() {
@@ -19,7 +30,9 @@ mod parser_tests {
                 attributes: None,
                 within_special_tag: None,
                 children: vec![],
+                span: None,
             }],
+            span: None,
         };
         assert_eq!(safe_parse_html(input).unwrap(), expected);
     }
@@ -44,7 +57,9 @@ mod parser_tests {
                         attributes: None,
                         within_special_tag: None,
                         children: vec![],
+                        span: None,
                     }],
+                    span: None,
                 },
                 Node {
                     tag_name: Some(H2),
@@ -57,9 +72,12 @@ mod parser_tests {
                         attributes: None,
                         within_special_tag: None,
                         children: vec![],
+                        span: None,
                     }],
+                    span: None,
                 },
             ],
+            span: None,
         };
         assert_eq!(safe_parse_html(input).unwrap(), expected);
     }
@@ -141,6 +159,7 @@ mod parser_tests {
             attributes: None,
             within_special_tag: None,
             children: vec![],
+            span: None,
         };
         assert_eq!(safe_parse_html(input).unwrap(), expected);
     }
@@ -167,7 +186,9 @@ mod parser_tests {
                         attributes: None,
                         within_special_tag: None,
                         children: vec![],
+                        span: None,
                     }],
+                    span: None,
                 },
                 Node {
                     tag_name: Some(Div),
@@ -175,8 +196,10 @@ mod parser_tests {
                     attributes: None,
                     within_special_tag: None,
                     children: vec![],
+                    span: None,
                 },
             ],
+            span: None,
         };
         assert_eq!(safe_parse_html(input).unwrap(), expected);
     }
@@ -188,7 +211,11 @@ mod parser_tests {
             safe_parse_html(input),
             Err(ParseHTMLError::MalformedTag(
                 "<div".to_string(),
-                MalformedTagError::MissingClosingBracket(16)
+                MalformedTagError::MissingClosingBracket(SourcePosition {
+                    offset: 16,
+                    line: 1,
+                    column: 17,
+                })
             ))
         );
     }
@@ -199,8 +226,12 @@ mod parser_tests {
         assert_eq!(
             safe_parse_html(input),
             Err(ParseHTMLError::MalformedTag(
-                "".to_string(),
-                MalformedTagError::MissingTagName(0)
+                "<>".to_string(),
+                MalformedTagError::MissingTagName(SourcePosition {
+                    offset: 0,
+                    line: 1,
+                    column: 1,
+                })
             ))
         );
     }
@@ -356,4 +387,1393 @@ mod parser_tests {
         };
         assert_eq!(safe_parse_html(input).unwrap(), expected);
     }
+
+    #[test]
+    fn character_references() {
+        let input = "<a href=\"/search?q=a&amp;b&copy=1\" title=\"&quot;quoted&quot;\">AT&amp;T &lt;3 &nbsp;&#8217;&#x1F600;&copy 2024</a>".to_string();
+        let mut attributes = Attributes::new();
+        attributes.insert(
+            "href".to_string(),
+            AttributeValues::from("/search?q=a&b&copy=1"),
+        );
+        attributes.insert("title".to_string(), AttributeValues::from("\"quoted\""));
+        let expected = Node {
+            tag_name: Some(A),
+            attributes: Some(attributes),
+            children: vec![Node {
+                tag_name: Some(Text),
+                value: Some("AT&T <3 \u{a0}\u{2019}\u{1F600}\u{a9} 2024".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(safe_parse_html(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn void_elements_without_trailing_slash() {
+        let input =
+            "<p>hello<br>world<img src=x><input type=text></p><hr><p>again</br></p>".to_string();
+        let mut img_attributes = Attributes::new();
+        img_attributes.insert("src".to_string(), AttributeValues::from("x"));
+        let mut input_attributes = Attributes::new();
+        input_attributes.insert("type".to_string(), AttributeValues::from("text"));
+        let expected = Node {
+            children: vec![
+                Node {
+                    tag_name: Some(P),
+                    children: vec![
+                        Node {
+                            tag_name: Some(Text),
+                            value: Some("hello".to_string()),
+                            ..Default::default()
+                        },
+                        Node {
+                            tag_name: Some(Br),
+                            ..Default::default()
+                        },
+                        Node {
+                            tag_name: Some(Text),
+                            value: Some("world".to_string()),
+                            ..Default::default()
+                        },
+                        Node {
+                            tag_name: Some(Unknown("img".to_string())),
+                            attributes: Some(img_attributes),
+                            ..Default::default()
+                        },
+                        Node {
+                            tag_name: Some(Unknown("input".to_string())),
+                            attributes: Some(input_attributes),
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                },
+                Node {
+                    tag_name: Some(Hr),
+                    ..Default::default()
+                },
+                Node {
+                    tag_name: Some(P),
+                    children: vec![
+                        Node {
+                            tag_name: Some(Text),
+                            value: Some("again".to_string()),
+                            ..Default::default()
+                        },
+                        Node {
+                            tag_name: Some(Br),
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(safe_parse_html(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn single_quoted_and_unquoted_attributes() {
+        let input =
+            "<a HREF='/x?a=\"b\"' class='y' data-x=1=2 title=\"it's\" download/ alt=\"\">link</a>"
+                .to_string();
+        let mut attributes = Attributes::new();
+        attributes.insert("href".to_string(), AttributeValues::from("/x?a=\"b\""));
+        attributes.insert("class".to_string(), AttributeValues::from("y"));
+        attributes.insert("data-x".to_string(), AttributeValues::from("1=2"));
+        attributes.insert("title".to_string(), AttributeValues::from("it's"));
+        attributes.insert("download".to_string(), AttributeValues::from(true));
+        attributes.insert("alt".to_string(), AttributeValues::from(""));
+        let expected = Node {
+            tag_name: Some(A),
+            attributes: Some(attributes),
+            children: vec![Node {
+                tag_name: Some(Text),
+                value: Some("link".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(safe_parse_html(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn closing_bracket_in_single_quoted_attribute() {
+        let input = "<img alt='a > b' src=x.png>".to_string();
+        let mut attributes = Attributes::new();
+        attributes.insert("alt".to_string(), AttributeValues::from("a > b"));
+        attributes.insert("src".to_string(), AttributeValues::from("x.png"));
+        let expected = Node {
+            tag_name: Some(Unknown("img".to_string())),
+            attributes: Some(attributes),
+            ..Default::default()
+        };
+        assert_eq!(safe_parse_html(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn forward_slash_in_unquoted_attribute_value() {
+        // the forward slash is a part of the value, so the tag is not self-closing
+        let input = "<a href=http://x.com/>link</a>".to_string();
+        let parsed = safe_parse_html(input).unwrap();
+        assert_eq!(
+            parsed
+                .attributes
+                .as_ref()
+                .and_then(|attributes| attributes.get_href()),
+            Some("http://x.com/".to_string())
+        );
+        assert_eq!(parsed.children.len(), 1);
+
+        let input = "<p>a<br/>b<span title=\"x\"/>c<img src=a.png />d</p>".to_string();
+        let parsed = safe_parse_html(input).unwrap();
+        let tags = parsed
+            .children
+            .iter()
+            .map(|child| (child.tag_name.clone(), child.children.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tags,
+            vec![
+                (Some(Text), 0),
+                (Some(Br), 0),
+                (Some(Text), 0),
+                (Some(Unknown("span".to_string())), 0),
+                (Some(Text), 0),
+                (Some(Unknown("img".to_string())), 0),
+                (Some(Text), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_attribute_value() {
+        let input = "<p>a<img alt=>b</p>";
+        let error = safe_parse_html(input.to_string()).unwrap_err();
+        assert!(matches!(
+            error,
+            ParseHTMLError::MalformedAttribute(
+                _,
+                MalformedAttributeError::MissingAttributeValue(SourcePosition {
+                    offset: 13,
+                    line: 1,
+                    column: 14,
+                })
+            )
+        ));
+
+        // the value is empty when recovering
+        let (parsed, warnings) = parse_html_lenient(input.to_string());
+        let kinds = warnings
+            .iter()
+            .map(|warning| (warning.kind, warning.position.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, vec![(ParseWarningKind::MissingAttributeValue, 13)]);
+        assert_eq!(
+            parsed.children[1].attributes.as_ref().unwrap().get("alt"),
+            Some(AttributeValues::from(""))
+        );
+    }
+
+    #[test]
+    fn mis_nested_formatting_elements() {
+        let input = "<b><i>x</b>y</i>".to_string();
+        let element = |tag: &str, children: Vec<Node>| Node {
+            tag_name: Some(Unknown(tag.to_string())),
+            children,
+            ..Default::default()
+        };
+        let text = |value: &str| Node {
+            tag_name: Some(Text),
+            value: Some(value.to_string()),
+            ..Default::default()
+        };
+        let expected = Node {
+            children: vec![
+                element("b", vec![element("i", vec![text("x")])]),
+                element("i", vec![text("y")]),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(safe_parse_html(input).unwrap(), expected);
+
+        let input = "<b>1<p>2</b>3</p>".to_string();
+        let expected = Node {
+            children: vec![
+                element("b", vec![text("1")]),
+                Node {
+                    tag_name: Some(P),
+                    children: vec![element("b", vec![text("2")]), text("3")],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(safe_parse_html(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn unmatched_closing_tags() {
+        let input = "<div>hello</span> world</div></p>".to_string();
+        let expected = Node {
+            tag_name: Some(Div),
+            children: vec![Node {
+                tag_name: Some(Text),
+                value: Some("hello world".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(safe_parse_html(input).unwrap(), expected);
+
+        let input = "<div><span>hello</div>world".to_string();
+        let expected = Node {
+            children: vec![
+                Node {
+                    tag_name: Some(Div),
+                    children: vec![Node {
+                        tag_name: Some(Unknown("span".to_string())),
+                        children: vec![Node {
+                            tag_name: Some(Text),
+                            value: Some("hello".to_string()),
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+                Node {
+                    tag_name: Some(Text),
+                    value: Some("world".to_string()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(safe_parse_html(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn implicit_end_tags() {
+        let text = |value: &str, within_special_tag: Option<Vec<NodeType>>| Node {
+            tag_name: Some(Text),
+            value: Some(value.to_string()),
+            within_special_tag,
+            ..Default::default()
+        };
+
+        let input = "<ul><li>one<li>two</ul><p>three<p>four".to_string();
+        let list_item = |value: &str| Node {
+            tag_name: Some(Li),
+            within_special_tag: Some(vec![Ul]),
+            children: vec![text(value, Some(vec![Ul]))],
+            ..Default::default()
+        };
+        let paragraph = |value: &str| Node {
+            tag_name: Some(P),
+            children: vec![text(value, None)],
+            ..Default::default()
+        };
+        let expected = Node {
+            children: vec![
+                Node {
+                    tag_name: Some(Ul),
+                    children: vec![list_item("one"), list_item("two")],
+                    ..Default::default()
+                },
+                paragraph("three"),
+                paragraph("four"),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(safe_parse_html(input).unwrap(), expected);
+
+        let input = "<table><tr><td>1<td>2<tr><th>3</table>".to_string();
+        let element = |tag: &str, children: Vec<Node>| Node {
+            tag_name: Some(Unknown(tag.to_string())),
+            children,
+            ..Default::default()
+        };
+        let expected = element(
+            "table",
+            vec![
+                element(
+                    "tr",
+                    vec![
+                        element("td", vec![text("1", None)]),
+                        element("td", vec![text("2", None)]),
+                    ],
+                ),
+                element("tr", vec![element("th", vec![text("3", None)])]),
+            ],
+        );
+        assert_eq!(safe_parse_html(input).unwrap(), expected);
+
+        let input = "<dl><dt>term<dd>definition<dt>other</dl>".to_string();
+        let expected = element(
+            "dl",
+            vec![
+                element("dt", vec![text("term", None)]),
+                element("dd", vec![text("definition", None)]),
+                element("dt", vec![text("other", None)]),
+            ],
+        );
+        assert_eq!(safe_parse_html(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn raw_text_elements() {
+        let input =
+            "<script>if (a < b && \"<div>\" != x) {}</script><textarea>a &amp; <b>b</b></TEXTAREA>"
+                .to_string();
+        let expected = Node {
+            children: vec![
+                Node {
+                    tag_name: Some(Script),
+                    children: vec![Node {
+                        tag_name: Some(Text),
+                        value: Some("if (a < b && \"<div>\" != x) {}".to_string()),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+                Node {
+                    tag_name: Some(Unknown("textarea".to_string())),
+                    children: vec![Node {
+                        tag_name: Some(Text),
+                        value: Some("a & <b>b</b>".to_string()),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(safe_parse_html(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn cdata_processing_instructions_and_bogus_comments() {
+        let input = "<?xml version=\"1.0\" encoding=\"UTF-8\"?><!ELEMENT br EMPTY><p>a <![CDATA[x < y]]> b</p>".to_string();
+        let expected = Node {
+            children: vec![
+                Node {
+                    tag_name: Some(Comment),
+                    value: Some("ELEMENT br EMPTY".to_string()),
+                    ..Default::default()
+                },
+                Node {
+                    tag_name: Some(P),
+                    children: vec![Node {
+                        tag_name: Some(Text),
+                        value: Some("a x < y b".to_string()),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(safe_parse_html(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn unterminated_cdata() {
+        let input = "<p><![CDATA[x < y</p>".to_string();
+        assert_eq!(
+            safe_parse_html(input),
+            Err(ParseHTMLError::MalformedTag(
+                "<![CDATA[x < y</p>".to_string(),
+                MalformedTagError::MissingClosingBracket(SourcePosition {
+                    offset: 3,
+                    line: 1,
+                    column: 4,
+                })
+            ))
+        );
+    }
+
+    #[test]
+    fn doctype() {
+        let parsed = safe_parse_html_document("<!doctype html><p>hello</p>".to_string()).unwrap();
+        assert_eq!(
+            parsed.doctype,
+            Some(Doctype {
+                name: Some("html".to_string()),
+                ..Default::default()
+            })
+        );
+        assert_eq!(parsed.quirks_mode(), QuirksMode::NoQuirks);
+        assert_eq!(parsed.root.tag_name, Some(P));
+
+        let input = "<!DOCTYPE HTML PUBLIC '-//W3C//DTD HTML 4.01 Transitional//EN' \"http://www.w3.org/TR/html4/loose.dtd\">".to_string();
+        let parsed = safe_parse_html_document(input).unwrap();
+        assert_eq!(
+            parsed.doctype,
+            Some(Doctype {
+                name: Some("html".to_string()),
+                public_id: Some("-//W3C//DTD HTML 4.01 Transitional//EN".to_string()),
+                system_id: Some("http://www.w3.org/TR/html4/loose.dtd".to_string()),
+                force_quirks: false,
+            })
+        );
+        assert_eq!(parsed.quirks_mode(), QuirksMode::LimitedQuirks);
+
+        let input = "<!DOCTYPE html PUBLIC \"-//W3C//DTD HTML 4.01 Transitional//EN\">".to_string();
+        assert_eq!(
+            safe_parse_html_document(input).unwrap().quirks_mode(),
+            QuirksMode::Quirks
+        );
+        let input = "<!DOCTYPE html SYSTEM \"about:legacy-compat>".to_string();
+        let parsed = safe_parse_html_document(input).unwrap();
+        assert!(parsed.doctype.unwrap().force_quirks);
+        let input = "<p>no doctype</p>".to_string();
+        let parsed = safe_parse_html_document(input).unwrap();
+        assert_eq!(parsed.doctype, None);
+        assert_eq!(parsed.quirks_mode(), QuirksMode::Quirks);
+    }
+
+    #[test]
+    fn truncated_doctype() {
+        let input = "<!DOCTYPE html".to_string();
+        assert_eq!(
+            safe_parse_html(input),
+            Err(ParseHTMLError::MalformedTag(
+                "<!DOCTYPE html".to_string(),
+                MalformedTagError::MissingClosingBracket(SourcePosition {
+                    offset: 0,
+                    line: 1,
+                    column: 1,
+                })
+            ))
+        );
+    }
+
+    #[test]
+    fn source_spans() {
+        let input = "<ul>\n  <li>one\n  <li>tw\u{e9}o</ul>\n<p>a<b>b</p>c</b><!-- x -->";
+        let config = ParseConfig {
+            record_spans: true,
+            ..Default::default()
+        };
+        let parsed = safe_parse_html_with_config(input.to_string(), &config).unwrap();
+        let source = |node: &Node| &input[node.span.unwrap().range()];
+
+        assert_eq!(source(&parsed), input);
+        let list = &parsed.children[0];
+        assert_eq!(source(list), "<ul>\n  <li>one\n  <li>tw\u{e9}o</ul>");
+        // list items closed implicitly end where the next one starts
+        assert_eq!(source(&list.children[0]), "<li>one\n  ");
+        assert_eq!(source(&list.children[1]), "<li>tw\u{e9}o");
+        let paragraph = &parsed.children[1];
+        assert_eq!(source(paragraph), "<p>a<b>b</p>");
+        assert_eq!(source(&paragraph.children[1]), "<b>b");
+        // the reopened formatting element starts at the text it was reopened for
+        assert_eq!(source(&parsed.children[2]), "c</b>");
+        assert_eq!(source(&parsed.children[3]), "<!-- x -->");
+
+        // columns count characters while offsets count bytes
+        let end_of_list = list.span.unwrap().end;
+        assert_eq!(
+            end_of_list,
+            SourcePosition {
+                offset: 31,
+                line: 3,
+                column: 16,
+            }
+        );
+    }
+
+    #[test]
+    fn no_source_spans_by_default() {
+        let parsed = safe_parse_html("<p>hello</p>".to_string()).unwrap();
+        assert_eq!(parsed.span, None);
+        assert_eq!(parsed.children[0].span, None);
+    }
+
+    #[test]
+    fn error_positions_and_bounded_snippets() {
+        let input = format!("<p>\n  <div {}", "a".repeat(100));
+        let error = safe_parse_html(input).unwrap_err();
+        assert_eq!(error.snippet(), format!("<div {}", "a".repeat(35)));
+        assert_eq!(
+            error.span().start,
+            SourcePosition {
+                offset: 6,
+                line: 2,
+                column: 3,
+            }
+        );
+        assert_eq!(error.span().end.column, 43);
+
+        let input = "<div>\n\t<img =\"a.png\">\n</div>".to_string();
+        let error = safe_parse_html(input.clone()).unwrap_err();
+        assert_eq!(
+            error,
+            ParseHTMLError::MalformedAttribute(
+                "=\"a.png\">".to_string(),
+                MalformedAttributeError::MissingAttributeName(SourcePosition {
+                    offset: 12,
+                    line: 2,
+                    column: 7,
+                })
+            )
+        );
+        assert_eq!(
+            error.to_string(),
+            "Malformed attribute: =\"a.png\"> - Missing attribute name at line 2, column 7"
+        );
+        assert_eq!(
+            error.render(&input),
+            "Malformed attribute: =\"a.png\"> - Missing attribute name at line 2, column 7
+  |
+2 | \t<img =\"a.png\">
+  | \t     ^^^^^^^^^"
+        );
+
+        // the errors compose with other error types through std::error::Error
+        let boxed: Box<dyn std::error::Error> = Box::new(error);
+        assert!(boxed.to_string().starts_with("Malformed attribute"));
+    }
+
+    #[test]
+    fn lenient_recovery() {
+        let input = "<div>a < b <> c</> d</ p><img =x src=\"y.png\"></div>\n<p>x<!-- never closed";
+        let (parsed, warnings) = parse_html_lenient(input.to_string());
+
+        let mut image_attributes = Attributes::new();
+        image_attributes.insert("=x".to_string(), AttributeValues::Bool(true));
+        image_attributes.insert("src".to_string(), AttributeValues::from("y.png"));
+        let expected = Node {
+            children: vec![
+                Node {
+                    tag_name: Some(Div),
+                    children: vec![
+                        Node {
+                            tag_name: Some(Text),
+                            value: Some("a < b <> c d".to_string()),
+                            ..Default::default()
+                        },
+                        Node {
+                            tag_name: Some(Comment),
+                            value: Some(" p".to_string()),
+                            ..Default::default()
+                        },
+                        Node {
+                            tag_name: Some(Unknown("img".to_string())),
+                            attributes: Some(image_attributes),
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                },
+                Node {
+                    tag_name: Some(P),
+                    children: vec![
+                        Node {
+                            tag_name: Some(Text),
+                            value: Some("x".to_string()),
+                            ..Default::default()
+                        },
+                        Node {
+                            tag_name: Some(Comment),
+                            value: Some(" never closed".to_string()),
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(parsed, expected);
+
+        let kinds_and_columns: Vec<_> = warnings
+            .iter()
+            .map(|warning| (warning.kind, warning.position.line, warning.position.column))
+            .collect();
+        assert_eq!(
+            kinds_and_columns,
+            vec![
+                (ParseWarningKind::MissingTagName, 1, 8),
+                (ParseWarningKind::MissingTagName, 1, 12),
+                (ParseWarningKind::MissingTagName, 1, 16),
+                (ParseWarningKind::MissingTagName, 1, 21),
+                (ParseWarningKind::MissingAttributeName, 1, 31),
+                (ParseWarningKind::MissingClosingBracket, 2, 5),
+            ]
+        );
+        assert_eq!(
+            warnings[4].to_string(),
+            "Missing attribute name at line 1, column 31"
+        );
+    }
+
+    #[test]
+    fn lenient_recovery_of_cut_off_input() {
+        let (parsed, warnings) = parse_html_lenient("<p>hello</p><div".to_string());
+        assert_eq!(parsed, safe_parse_html("<p>hello</p>".to_string()).unwrap());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, ParseWarningKind::MissingClosingBracket);
+
+        let config = ParseConfig {
+            lenient: true,
+            ..Default::default()
+        };
+        let parsed = safe_parse_html_document("<!DOCTYPE html".to_string());
+        assert!(parsed.is_err());
+        let parsed =
+            safe_parse_html_document_with_config("<!DOCTYPE html".to_string(), &config).unwrap();
+        assert_eq!(parsed.quirks_mode(), QuirksMode::Quirks);
+        assert_eq!(parsed.warnings.len(), 1);
+    }
+
+    #[test]
+    fn streaming_matches_whole_input() {
+        let input = "<!DOCTYPE html>\n<html><head><title>Caf\u{e9} &amp; \u{1F600}</title>\
+<script>if (a < b) { x = \"</p>\"; }</script></head>\n<body><!-- a -- comment -->\
+<p class='intro' data-x=1>Tom &amp; Jerry&#8217;s <b>caf\u{e9}<i>s</b> &copy 2024</i>\
+<ul><li>one<li>two</ul><![CDATA[1 < 2]]><br/><img src=\"a>b.png\" alt=x></body></html>";
+        let config = ParseConfig {
+            record_spans: true,
+            ..Default::default()
+        };
+        let expected = safe_parse_html_document_with_config(input.to_string(), &config).unwrap();
+
+        for chunk_size in 1..=16 {
+            let mut parser = StreamingParser::with_config(config.clone());
+            for chunk in input.as_bytes().chunks(chunk_size) {
+                parser.feed(chunk).unwrap();
+            }
+            let parsed = parser.finish().unwrap();
+            assert_eq!(parsed.root, expected.root, "chunk size {}", chunk_size);
+            assert_eq!(parsed.doctype, expected.doctype);
+        }
+
+        let parsed = safe_parse_html_reader(input.as_bytes()).unwrap();
+        assert_eq!(parsed, safe_parse_html(input.to_string()).unwrap());
+
+        let input = "<div>a < b <> c</> d</ p><img =x src=\"y.png\"></div>\n<p>x<!-- never closed";
+        let config = ParseConfig {
+            lenient: true,
+            ..Default::default()
+        };
+        let expected = safe_parse_html_document_with_config(input.to_string(), &config).unwrap();
+        for chunk_size in 1..=8 {
+            let mut parser = StreamingParser::with_config(config.clone());
+            for chunk in input.as_bytes().chunks(chunk_size) {
+                parser.feed(chunk).unwrap();
+            }
+            let parsed = parser.finish().unwrap();
+            assert_eq!(parsed.root, expected.root, "chunk size {}", chunk_size);
+            assert_eq!(parsed.warnings, expected.warnings);
+        }
+    }
+
+    #[test]
+    fn streaming_invalid_utf8_and_errors() {
+        let mut parser = StreamingParser::new();
+        parser.feed(b"<p>caf\xC3").unwrap();
+        parser.feed(b"\xA9 \xFF</p><p>\xE2\x82").unwrap();
+        let parsed = parser.finish().unwrap();
+        let expected = safe_parse_html("<p>caf\u{e9} \u{FFFD}</p><p>\u{FFFD}".to_string()).unwrap();
+        assert_eq!(parsed.root, expected);
+
+        let mut parser = StreamingParser::new();
+        parser.feed(b"<div>hello</div>").unwrap();
+        parser.feed(b"<div").unwrap();
+        assert_eq!(
+            parser.finish(),
+            Err(ParseHTMLError::MalformedTag(
+                "<div".to_string(),
+                MalformedTagError::MissingClosingBracket(SourcePosition {
+                    offset: 16,
+                    line: 1,
+                    column: 17,
+                })
+            ))
+        );
+
+        let error = safe_parse_html_reader("<p>hello</p><>".as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn streaming_positions_across_chunks() {
+        let input =
+            "<div>\n<p>one</p>\n<p title=\"a\nb\">two</p>\n<b>a < b</b>\n<p>three</>\n</div>";
+        let config = ParseConfig {
+            lenient: true,
+            record_spans: true,
+            ..Default::default()
+        };
+        let expected = safe_parse_html_document_with_config(input.to_string(), &config).unwrap();
+        let lines = expected
+            .warnings
+            .iter()
+            .map(|warning| (warning.kind, warning.position.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                (ParseWarningKind::MissingTagName, 5),
+                (ParseWarningKind::MissingTagName, 6),
+            ]
+        );
+
+        for chunk_size in 1..=16 {
+            let mut parser = StreamingParser::with_config(config.clone());
+            for chunk in input.as_bytes().chunks(chunk_size) {
+                parser.feed(chunk).unwrap();
+            }
+            let parsed = parser.finish().unwrap();
+            assert_eq!(
+                parsed.warnings, expected.warnings,
+                "chunk size {}",
+                chunk_size
+            );
+            assert_eq!(parsed.root, expected.root, "chunk size {}", chunk_size);
+        }
+
+        // an error in a later chunk is positioned in the whole input
+        let mut parser = StreamingParser::new();
+        parser.feed_str("<div>\n<p>one</p>\n").unwrap();
+        parser.feed_str("<p>two</p>\n  <").unwrap();
+        let error = parser.feed_str("> </div>").unwrap_err();
+        assert_eq!(
+            error.position(),
+            SourcePosition {
+                offset: 30,
+                line: 4,
+                column: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn streaming_long_unterminated_tokens() {
+        // a token cut off by the end of every chunk is searched from where the last chunk stopped,
+        // so feeding it in small chunks takes linear time
+        let content = "a-b]]> c".repeat(40_000);
+        let tokens = [
+            ("<!--", "-->"),
+            ("<script>", "</script>"),
+            ("<textarea>", "</textarea >"),
+            ("<![CDATA[", "]]>"),
+            ("<p title='", "'>"),
+            ("<!DOCTYPE", ">"),
+            ("", "<br>"),
+        ];
+        for (start, end) in tokens {
+            let content = match start {
+                "<![CDATA[" => content.replace("]]>", "]>"),
+                "<p title='" | "<!DOCTYPE" | "" => content.replace('>', ""),
+                _ => content.clone(),
+            };
+            let input = format!("<div>{}{}{}x</div>", start, content, end);
+            let expected = safe_parse_html_document(input.clone()).unwrap();
+            let mut parser = StreamingParser::new();
+            for chunk in input.as_bytes().chunks(7) {
+                parser.feed(chunk).unwrap();
+            }
+            let parsed = parser.finish().unwrap();
+            assert_eq!(parsed.root, expected.root, "{}", start);
+        }
+    }
+
+    #[test]
+    fn borrowed_parse_tree() {
+        let input = "<!-- note --><div class=\"a\"><p>Tom &amp; Jerry</p><pre>let x;</pre><script>a < b</script><textarea>&lt;b&gt;</textarea><![CDATA[1 < 2]]></div>";
+        let borrowed = safe_parse_html_borrowed(input).unwrap();
+        let owned = safe_parse_html(input.to_string()).unwrap();
+        assert_eq!(borrowed.clone().into_owned(), owned);
+        assert_eq!(Node::from(borrowed.clone()), owned);
+
+        // only the texts with character references are decoded into owned strings
+        let div = &borrowed.children[1];
+        assert!(matches!(
+            borrowed.children[0].value,
+            Some(Cow::Borrowed(" note "))
+        ));
+        assert!(matches!(
+            div.children[0].children[0].value,
+            Some(Cow::Owned(_))
+        ));
+        assert!(matches!(
+            div.children[1].children[0].value,
+            Some(Cow::Borrowed("let x;"))
+        ));
+        assert!(matches!(
+            div.children[2].children[0].value,
+            Some(Cow::Borrowed("a < b"))
+        ));
+        assert!(matches!(
+            div.children[3].children[0].value,
+            Some(Cow::Owned(_))
+        ));
+        assert!(matches!(
+            div.children[4].value,
+            Some(Cow::Borrowed("1 < 2"))
+        ));
+
+        let config = ParseConfig {
+            record_spans: true,
+            lenient: true,
+            ..Default::default()
+        };
+        let input = "<p>1 < 2</p>text";
+        let borrowed = safe_parse_html_borrowed_with_config(input, &config).unwrap();
+        let owned = safe_parse_html_with_config(input.to_string(), &config).unwrap();
+        assert_eq!(borrowed.into_owned(), owned);
+    }
+
+    #[test]
+    fn whitespace_collapsing() {
+        let texts = |node: &Node| {
+            let mut texts = Vec::new();
+            let mut stack = vec![node];
+            while let Some(node) = stack.pop() {
+                if node.tag_name == Some(Text) {
+                    texts.push(node.value.clone().unwrap_or_default());
+                }
+                stack.extend(node.children.iter().rev());
+            }
+            texts
+        };
+        let cases = [
+            ("<p>\n  hello\n\tworld  \n</p>", vec!["hello world"]),
+            ("<p><b>a</b> <i>b</i></p>", vec!["a", " ", "b"]),
+            ("<p>a <b> b </b> c</p>", vec!["a ", "b ", "c"]),
+            ("<p>a<br>\n  b <br/> </p>", vec!["a", "b"]),
+            ("<div>a <p>b</p> c</div>", vec!["a", "b", "c"]),
+            ("<p>a <img src=x.png> b</p>", vec!["a ", " b"]),
+            (
+                "<ul>\n  <li>one</li>\n  <li>two </li>\n</ul>",
+                vec!["one", "two"],
+            ),
+            ("<p>a <!-- note --> b</p>", vec!["a ", "b"]),
+            ("<pre>  a\n   b  </pre>", vec!["  a\n   b  "]),
+            ("<pre><code>\n  let x;\n</code></pre>", vec!["\n  let x;\n"]),
+            (
+                "<p>a <textarea>  b\n </textarea> c</p>",
+                vec!["a ", "  b\n ", " c"],
+            ),
+            ("<p>a <code>  b  </code> c</p>", vec!["a ", "b ", "c"]),
+            ("<p>a\u{a0} b</p>", vec!["a\u{a0} b"]),
+            ("  \n  ", vec![]),
+        ];
+        for (input, expected) in cases {
+            let parsed = safe_parse_html(input.to_string()).unwrap();
+            assert_eq!(texts(&parsed), expected, "input: {:?}", input);
+        }
+
+        // whitespace between elements doesn't reopen the formatting elements
+        let input = "<p><strong>a</p>\n<p>b</p>".to_string();
+        let parsed = safe_parse_html(input).unwrap();
+        assert_eq!(parsed.children[1].children[0].tag_name, Some(Strong));
+
+        // a text which is only trimmed stays borrowed from the input
+        let parsed = safe_parse_html_borrowed("<p>\n  hello\n</p>").unwrap();
+        assert!(matches!(
+            parsed.children[0].value,
+            Some(Cow::Borrowed("hello"))
+        ));
+    }
+
+    #[test]
+    fn fragment_with_context() {
+        let input = "<li>one</li><li>two</ul><li>three".to_string();
+        let parsed = safe_parse_html_fragment(input, Ul).unwrap();
+        assert_eq!(parsed.tag_name, Some(Ul));
+        assert_eq!(parsed.within_special_tag, None);
+        assert_eq!(parsed.children.len(), 3);
+        for li in &parsed.children {
+            assert_eq!(li.tag_name, Some(Li));
+            assert_eq!(li.within_special_tag, Some(vec![Ul]));
+            assert_eq!(li.children[0].within_special_tag, Some(vec![Ul]));
+        }
+
+        // the context can't be closed by the fragment
+        let input = "one</p><p>two".to_string();
+        let parsed = safe_parse_html_fragment(input, P).unwrap();
+        assert_eq!(parsed.tag_name, Some(P));
+        assert_eq!(parsed.children[0].value, Some("one".to_string()));
+        assert_eq!(parsed.children[1].tag_name, Some(P));
+
+        let input = "<td>a</td><td>b</td>".to_string();
+        let parsed = safe_parse_html_fragment(input, Unknown("tr".to_string())).unwrap();
+        let cells = parsed
+            .children
+            .iter()
+            .map(|cell| cell.tag_name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(cells, vec![Some(Unknown("td".to_string())); 2]);
+
+        // whitespace is kept in a pre
+        let input = "  let x;\n  <b>let y;</b>".to_string();
+        let parsed = safe_parse_html_fragment(input, Pre).unwrap();
+        assert_eq!(parsed.children[0].value, Some("  let x;\n  ".to_string()));
+
+        // the content of a raw text context is a text
+        let input = "<b>Tom &amp; Jerry</b></textarea>".to_string();
+        let parsed = safe_parse_html_fragment(input, Unknown("textarea".to_string())).unwrap();
+        assert_eq!(
+            parsed.children[0].value,
+            Some("<b>Tom & Jerry</b></textarea>".to_string())
+        );
+
+        let config = ParseConfig {
+            record_spans: true,
+            lenient: true,
+            ..Default::default()
+        };
+        let input = "<li>1 < 2".to_string();
+        let parsed = safe_parse_html_fragment_with_config(input, Ol, &config).unwrap();
+        assert_eq!(parsed.span.unwrap().range(), 0..9);
+        assert_eq!(parsed.children[0].span.unwrap().range(), 0..9);
+        assert_eq!(
+            parsed.children[0].children[0].value,
+            Some("1 < 2".to_string())
+        );
+    }
+
+    #[test]
+    fn foreign_content() {
+        let svg = |name: &str| Some(Foreign(Namespace::Svg, name.to_string()));
+        let math = |name: &str| Some(Foreign(Namespace::MathMl, name.to_string()));
+
+        let input = "<p><svg viewbox=\"0 0 10 10\"><lineargradient gradientunits=\"userSpaceOnUse\"/><circle r=\"5\"/><foreignobject><p>html</p></foreignobject></svg></p>".to_string();
+        let parsed = safe_parse_html(input).unwrap();
+        let svg_node = &parsed.children[0];
+        assert_eq!(svg_node.tag_name, svg("svg"));
+        assert_eq!(svg_node.namespace(), Namespace::Svg);
+        assert_eq!(
+            svg_node.attributes.as_ref().unwrap().get("viewBox"),
+            Some(AttributeValues::from("0 0 10 10"))
+        );
+        // any foreign element can be self-closing
+        let children = svg_node
+            .children
+            .iter()
+            .map(|child| child.tag_name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            children,
+            vec![svg("linearGradient"), svg("circle"), svg("foreignObject")]
+        );
+        assert!(svg_node.children[0]
+            .attributes
+            .as_ref()
+            .unwrap()
+            .contains("gradientUnits"));
+        // the content of an integration point is HTML
+        let html = &svg_node.children[2].children[0];
+        assert_eq!(html.tag_name, Some(P));
+        assert_eq!(html.namespace(), Namespace::Html);
+        assert_eq!(html.children[0].value, Some("html".to_string()));
+
+        // <title> and <style> are not raw text elements in SVG
+        let input = "<svg><title><b>x</b></title><style>a { }</style></svg>".to_string();
+        let parsed = safe_parse_html(input).unwrap();
+        assert_eq!(parsed.children[0].tag_name, svg("title"));
+        assert_eq!(
+            parsed.children[0].children[0].tag_name,
+            Some(Unknown("b".to_string()))
+        );
+        assert_eq!(parsed.children[1].tag_name, svg("style"));
+
+        // an HTML element breaks out of foreign content
+        let input = "<div><svg><g><p>text</p></svg></div>".to_string();
+        let parsed = safe_parse_html(input).unwrap();
+        assert_eq!(parsed.children.len(), 2);
+        assert_eq!(parsed.children[0].tag_name, svg("svg"));
+        assert_eq!(parsed.children[0].children[0].tag_name, svg("g"));
+        assert_eq!(parsed.children[1].tag_name, Some(P));
+
+        // closing tags are matched case-insensitively
+        let input = "<svg><clipPath><rect/></CLIPPATH><text>a</text></svg><p>b</p>".to_string();
+        let parsed = safe_parse_html(input).unwrap();
+        assert_eq!(parsed.children[0].children.len(), 2);
+        assert_eq!(parsed.children[0].children[1].tag_name, svg("text"));
+        assert_eq!(parsed.children[1].tag_name, Some(P));
+
+        let input = "<math definitionurl=\"x\"><mi><b>x</b></mi><mo>=</mo><mn>1</mn><annotation-xml encoding=\"text/html\"><div>one</div></annotation-xml></math>".to_string();
+        let parsed = safe_parse_html(input).unwrap();
+        assert_eq!(parsed.tag_name, math("math"));
+        assert!(parsed
+            .attributes
+            .as_ref()
+            .unwrap()
+            .contains("definitionURL"));
+        let children = parsed
+            .children
+            .iter()
+            .map(|child| child.tag_name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            children,
+            vec![math("mi"), math("mo"), math("mn"), math("annotation-xml")]
+        );
+        assert_eq!(
+            parsed.children[0].children[0].tag_name,
+            Some(Unknown("b".to_string()))
+        );
+        assert_eq!(parsed.children[3].children[0].tag_name, Some(Div));
+    }
+
+    #[test]
+    fn qualified_names() {
+        let input = "<ac:structured-macro ac:name=\"code\"><o:p>word</o:p></ac:structured-macro>"
+            .to_string();
+        let parsed = safe_parse_html(input).unwrap();
+        assert_eq!(
+            parsed.qual_name(),
+            Some(QualName::new(
+                Some("ac".to_string()),
+                "structured-macro".to_string(),
+                Some(Namespace::Html)
+            ))
+        );
+        let attributes = parsed.attributes.as_ref().unwrap();
+        let name = attributes.qual_names().next().unwrap();
+        assert_eq!(name.prefix.as_deref(), Some("ac"));
+        assert_eq!(name.local, "name");
+        assert_eq!(name.namespace, None);
+        assert_eq!(
+            attributes.get_qualified(&name),
+            Some(AttributeValues::from("code"))
+        );
+        let child = parsed.children[0].qual_name().unwrap();
+        assert_eq!(
+            (child.prefix.as_deref(), child.local.as_str()),
+            (Some("o"), "p")
+        );
+        assert_eq!(child.to_string(), "o:p");
+        assert_eq!(parsed.children[0].children[0].qual_name(), None);
+
+        let input =
+            "<svg xmlns:xlink=\"http://www.w3.org/1999/xlink\"><use xlink:href=\"#icon\"/></svg>"
+                .to_string();
+        let parsed = safe_parse_html(input).unwrap();
+        let name = parsed.qual_name().unwrap();
+        assert_eq!((name.prefix, name.namespace), (None, Some(Namespace::Svg)));
+        let mut names = parsed.attributes.as_ref().unwrap().qual_names();
+        assert_eq!(names.next().unwrap().namespace, Some(Namespace::XmlNs));
+        let attributes = parsed.children[0].attributes.as_ref().unwrap();
+        let href = QualName::attribute("xlink:href");
+        assert_eq!(href.namespace, Some(Namespace::XLink));
+        assert_eq!(
+            attributes.get_qualified(&href),
+            Some(AttributeValues::from("#icon"))
+        );
+        assert_eq!(QualName::attribute(":x").prefix, None);
+
+        let custom_elements = ["my-card", "x-\u{e9}l\u{e9}ment", "a-1.b_c"];
+        for name in custom_elements {
+            assert!(Unknown(name.to_string()).is_custom_element(), "{}", name);
+        }
+        let other_elements = ["card", "-card", "1-card", "font-face", "my card-"];
+        for name in other_elements {
+            assert!(!Unknown(name.to_string()).is_custom_element(), "{}", name);
+        }
+        assert!(!Foreign(Namespace::Svg, "my-card".to_string()).is_custom_element());
+    }
+
+    #[test]
+    fn typed_attributes() {
+        let input = "<ol start=\" 3 \"><li value=\"-2\">a</li></ol><table><tr><td colspan=\"2\" rowspan=\"x\" width=\"50%\">b</td></tr></table><input type=\"checkbox\" checked=\"checked\" value=\"007\"><details open=\"\" hidden=\"until-found\"></details>".to_string();
+        let parsed = safe_parse_html(input).unwrap();
+        let attribute = |node: &Node, key: &str| node.attributes.as_ref().unwrap().get(key);
+
+        let ol = &parsed.children[0];
+        assert_eq!(attribute(ol, "start"), Some(AttributeValues::Number(3)));
+        assert_eq!(
+            attribute(&ol.children[0], "value"),
+            Some(AttributeValues::Number(-2))
+        );
+        let td = &parsed.children[1].children[0].children[0];
+        assert_eq!(attribute(td, "colspan"), Some(AttributeValues::Number(2)));
+        // invalid numbers are kept as they are
+        assert_eq!(attribute(td, "rowspan"), Some(AttributeValues::from("x")));
+        assert_eq!(attribute(td, "width"), Some(AttributeValues::from("50%")));
+        let input_node = &parsed.children[2];
+        assert_eq!(
+            attribute(input_node, "checked"),
+            Some(AttributeValues::Bool(true))
+        );
+        // the value of an input is a text
+        assert_eq!(
+            attribute(input_node, "value"),
+            Some(AttributeValues::from("007"))
+        );
+        let details = &parsed.children[3];
+        assert_eq!(
+            attribute(details, "open"),
+            Some(AttributeValues::Bool(true))
+        );
+        assert_eq!(
+            attribute(details, "hidden"),
+            Some(AttributeValues::from("until-found"))
+        );
+    }
+
+    #[test]
+    fn duplicate_attributes() {
+        let input = "<p class=\"a\" id=\"x\" class=\"b\" CLASS=\"c\">text</p>";
+        let parsed = safe_parse_html(input.to_string()).unwrap();
+        let attributes = parsed.attributes.unwrap();
+        assert_eq!(attributes.get_class(), Some(&"a".to_string()));
+        assert_eq!(attributes.get_id(), Some(&"x".to_string()));
+
+        let config = ParseConfig {
+            duplicate_attributes: DuplicateAttributePolicy::LastWins,
+            ..Default::default()
+        };
+        let parsed = safe_parse_html_with_config(input.to_string(), &config).unwrap();
+        assert_eq!(
+            parsed.attributes.unwrap().get_class(),
+            Some(&"c".to_string())
+        );
+
+        let mut config = ParseConfig {
+            duplicate_attributes: DuplicateAttributePolicy::Error,
+            ..Default::default()
+        };
+        let error = safe_parse_html_with_config(input.to_string(), &config).unwrap_err();
+        assert_eq!(
+            error,
+            ParseHTMLError::MalformedAttribute(
+                "class=\"b\" CLASS=\"c\">text</p>".to_string(),
+                MalformedAttributeError::DuplicateAttribute(SourcePosition {
+                    offset: 20,
+                    line: 1,
+                    column: 21,
+                }),
+            )
+        );
+
+        // the first attribute is kept when recovering
+        config.lenient = true;
+        let parsed = safe_parse_html_document_with_config(input.to_string(), &config).unwrap();
+        let kinds = parsed
+            .warnings
+            .iter()
+            .map(|warning| (warning.kind, warning.position.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (ParseWarningKind::DuplicateAttribute, 20),
+                (ParseWarningKind::DuplicateAttribute, 30)
+            ]
+        );
+        assert_eq!(
+            parsed.root.attributes.unwrap().get_class(),
+            Some(&"a".to_string())
+        );
+    }
+
+    #[test]
+    fn resource_limits() {
+        let config = |limits: Limits| ParseConfig {
+            limits,
+            ..Default::default()
+        };
+        let limit_error = |input: &str, limits: Limits| match safe_parse_html_with_config(
+            input.to_string(),
+            &config(limits),
+        ) {
+            Err(ParseHTMLError::LimitExceeded(_, error)) => error,
+            result => panic!("expected a limit error, got {:?}", result),
+        };
+
+        let limits = Limits {
+            max_input_size: 7,
+            ..Default::default()
+        };
+        let error = limit_error("<p>caf\u{e9}</p><p>x</p>", limits);
+        assert!(matches!(error, LimitExceededError::InputSize(position) if position.offset == 6));
+        assert!(safe_parse_html_with_config("<p></p>".to_string(), &config(limits)).is_ok());
+
+        let limits = Limits {
+            max_depth: 3,
+            ..Default::default()
+        };
+        let error = limit_error("<div><div><div><br><div>a</div></div></div></div>", limits);
+        assert_eq!(error.position().offset, 19);
+        assert!(matches!(error, LimitExceededError::Depth(_)));
+        // the default depth is far from exhausting the stack
+        let input = "<div>".repeat(100_000);
+        let error = limit_error(&input, Limits::default());
+        assert_eq!(error.position().offset, 512 * 5);
+
+        let limits = Limits {
+            max_nodes: 5,
+            ..Default::default()
+        };
+        let error = limit_error("<p>a</p><p>b</p><p>c</p>", limits);
+        assert!(matches!(error, LimitExceededError::NodeCount(position) if position.offset == 19));
+        // the reopened formatting elements are counted
+        let error = limit_error("<p><b>a<p>b<p>c", limits);
+        assert!(matches!(error, LimitExceededError::NodeCount(position) if position.offset == 10));
+
+        let limits = Limits {
+            max_attributes: 2,
+            ..Default::default()
+        };
+        let error = limit_error("<p a b a c>x</p>", limits);
+        assert!(
+            matches!(error, LimitExceededError::AttributeCount(position) if position.offset == 9)
+        );
+
+        let input = "<div>".repeat(1000);
+        let error = safe_parse_html(input).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Limit exceeded: <div><div><div><div><div><div><div><div> - Elements nested too deep at line 1, column 2561"
+        );
+
+        // the size of the input fed to a streaming parser is limited as a whole
+        let mut parser = StreamingParser::with_config(config(Limits {
+            max_input_size: 16,
+            ..Default::default()
+        }));
+        parser.feed_str("<p>hello</p>").unwrap();
+        let error = parser.feed_str("<p>world</p>").unwrap_err();
+        assert_eq!(error.position().offset, 16);
+
+        // the limits of the tree are positioned in the whole input when streaming
+        let mut parser = StreamingParser::with_config(ParseConfig {
+            record_spans: true,
+            ..config(Limits {
+                max_depth: 2,
+                ..Default::default()
+            })
+        });
+        parser.feed_str("<div>\n<div>\n").unwrap();
+        let error = parser.feed_str("  <p>x</p>").unwrap_err();
+        assert_eq!(
+            error.position(),
+            SourcePosition {
+                offset: 14,
+                line: 3,
+                column: 3,
+            }
+        );
+
+        let input = "<div>".repeat(2_000);
+        let parsed = safe_parse_html_with_config(input, &config(Limits::unlimited())).unwrap();
+        assert_eq!(parsed.tag_name, Some(Div));
+    }
+
+    #[test]
+    fn lenient_limits() {
+        let config = |limits: Limits| ParseConfig {
+            lenient: true,
+            limits,
+            ..Default::default()
+        };
+
+        // the elements nested too deep are skipped, keeping the content before and inside them
+        let input = format!("<p>intro</p>{}deep", "<div>".repeat(600));
+        let (parsed, warnings) = parse_html_lenient(input);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, ParseWarningKind::LimitExceeded);
+        assert_eq!(warnings[0].position.offset, 12 + 512 * 5);
+        assert_eq!(
+            parsed.children[0].children[0].value,
+            Some("intro".to_string())
+        );
+        let mut depth = 0;
+        let mut node = &parsed.children[1];
+        while node.tag_name == Some(Div) {
+            depth += 1;
+            node = &node.children[0];
+        }
+        assert_eq!(depth, 512);
+        assert_eq!(node.value, Some("deep".to_string()));
+
+        // the parsing stops at the tag that exceeded the limit, keeping the nodes before it
+        let input = "<p>a</p><p>b</p><p>c</p>".to_string();
+        let parsed = safe_parse_html_document_with_config(
+            input,
+            &config(Limits {
+                max_nodes: 4,
+                ..Default::default()
+            }),
+        )
+        .unwrap();
+        assert_eq!(parsed.warnings[0].position.offset, 16);
+        let texts: Vec<_> = parsed.root.children[..2]
+            .iter()
+            .map(|p| p.children[0].value.as_deref())
+            .collect();
+        assert_eq!(texts, vec![Some("a"), Some("b")]);
+        assert!(parsed.root.children[2].children.is_empty());
+
+        // the attributes over the limit are skipped
+        let input = "<p a b c d>x</p>".to_string();
+        let parsed = safe_parse_html_document_with_config(
+            input,
+            &config(Limits {
+                max_attributes: 2,
+                ..Default::default()
+            }),
+        )
+        .unwrap();
+        assert_eq!(parsed.warnings.len(), 1);
+        assert_eq!(parsed.warnings[0].position.offset, 7);
+        let attributes = parsed.root.attributes.unwrap();
+        assert!(attributes.contains("a") && attributes.contains("b"));
+        assert!(!attributes.contains("c") && !attributes.contains("d"));
+        assert_eq!(parsed.root.children[0].value, Some("x".to_string()));
+
+        // the input past the limit is ignored
+        let input = "<p>hello</p><p>world</p>".to_string();
+        let parsed = safe_parse_html_document_with_config(
+            input,
+            &config(Limits {
+                max_input_size: 12,
+                ..Default::default()
+            }),
+        )
+        .unwrap();
+        assert_eq!(parsed.warnings[0].position.offset, 12);
+        assert_eq!(parsed.root.tag_name, Some(P));
+        assert_eq!(parsed.root.children[0].value, Some("hello".to_string()));
+
+        // the input past the limit is ignored when streaming too
+        let mut parser = StreamingParser::with_config(config(Limits {
+            max_input_size: 16,
+            ..Default::default()
+        }));
+        parser.feed_str("<p>hello</p>").unwrap();
+        parser.feed_str("<p>world</p>").unwrap();
+        parser.feed_str("<p>again</p>").unwrap();
+        let parsed = parser.finish().unwrap();
+        assert_eq!(parsed.warnings.len(), 1);
+        assert_eq!(parsed.warnings[0].position.offset, 16);
+        assert_eq!(parsed.root.children.len(), 2);
+        assert_eq!(
+            parsed.root.children[1].children[0].value,
+            Some("w".to_string())
+        );
+    }
+
+    #[test]
+    fn multibyte_characters_in_tags() {
+        // the slash of a self-closing tag is found after multibyte characters
+        let input = "<svg><circle title=\"日本\"/><rect/></svg>".to_string();
+        let parsed = safe_parse_html(input).unwrap();
+        assert_eq!(parsed.children.len(), 2);
+
+        // a closing tag without a name is read as a comment up to the end of the input
+        let (parsed, warnings) = parse_html_lenient("a</😀".to_string());
+        assert_eq!(warnings[0].kind, ParseWarningKind::MissingTagName);
+        assert_eq!(parsed.children[1].tag_name, Some(Comment));
+        assert_eq!(parsed.children[1].value, Some("😀".to_string()));
+    }
 }
@@ -0,0 +1,201 @@
+#[cfg(test)]
+mod property_tests {
+    use html2md_rs::{
+        parser::{
+            parse_html_lenient, safe_parse_html, safe_parse_html_borrowed, safe_parse_html_bytes,
+            safe_parse_html_fragment, safe_parse_html_with_config, StreamingParser,
+        },
+        selector::Selector,
+        structs::{NodeType, ParseConfig},
+        to_html::to_html,
+        to_md::{safe_from_html_to_md, to_md},
+    };
+    use proptest::prelude::*;
+
+    /// The pieces of HTML that make up the generated inputs, which are likely to hit the edge cases of the parser
+    const PIECES: [&str; 36] = [
+        "<",
+        ">",
+        "/",
+        "</",
+        "/>",
+        "<!--",
+        "-->",
+        "<!",
+        "<?",
+        "<!DOCTYPE",
+        " PUBLIC ",
+        "\"",
+        "'",
+        "=",
+        " ",
+        "\n",
+        "p",
+        "li",
+        "ul",
+        "b",
+        "a href",
+        "table",
+        "td",
+        "svg",
+        "math",
+        "mi",
+        "script",
+        "textarea",
+        "pre",
+        "br",
+        "&",
+        "&amp;",
+        "&#x",
+        ";",
+        "<![CDATA[",
+        "]]>",
+    ];
+
+    /// Generates HTML-like strings mixing the pieces with arbitrary characters (i.e. `</😀`)
+    fn html() -> impl Strategy<Value = String> {
+        let piece = prop_oneof![
+            3 => proptest::sample::select(&PIECES[..]).prop_map(String::from),
+            1 => any::<char>().prop_map(String::from),
+        ];
+        proptest::collection::vec(piece, 0..64).prop_map(|pieces| pieces.concat())
+    }
+
+    /// The pieces of CSS selectors that make up the generated selectors
+    const SELECTOR_PIECES: [&str; 24] = [
+        "p",
+        "li",
+        "*",
+        ".a",
+        "#b",
+        "[href",
+        "]",
+        "^=",
+        "~=",
+        "|=",
+        "=",
+        "\"x\"",
+        " i",
+        " ",
+        ">",
+        "+",
+        "~",
+        ",",
+        ":not(",
+        ":first-child",
+        ":nth-child(",
+        "2n+1",
+        ")",
+        "\\",
+    ];
+
+    /// Generates selector-like strings mixing the pieces with arbitrary characters
+    fn selector() -> impl Strategy<Value = String> {
+        let piece = prop_oneof![
+            3 => proptest::sample::select(&SELECTOR_PIECES[..]).prop_map(String::from),
+            1 => any::<char>().prop_map(String::from),
+        ];
+        proptest::collection::vec(piece, 0..16).prop_map(|pieces| pieces.concat())
+    }
+
+    /// Generates elements whose names and attribute values contain the characters that need care when written back,
+    /// such as quotation marks, equal signs and character references (i.e. `<a"b =c d='&amp;"'>`)
+    fn element() -> impl Strategy<Value = String> {
+        let text = "[a-z <>&;'\"\u{A0}]{0,8}";
+        let attribute = (
+            "[a-z=\"'<&;!-][a-z=\"'<&;!-]{0,3}",
+            proptest::option::of("[a-z =\"'<>&;/\u{A0}-]{0,6}"),
+        )
+            .prop_map(|(name, value)| match value {
+                Some(value) => format!(" {}='{}'", name, value.replace('\'', "&#39;")),
+                None => format!(" {}", name),
+            });
+        text.prop_recursive(3, 16, 4, move |children| {
+            (
+                "[a-z][a-z=\"'<&;!-]{0,3}",
+                proptest::collection::vec(attribute.clone(), 0..4),
+                proptest::collection::vec(children, 0..4),
+            )
+                .prop_map(|(name, attributes, children)| {
+                    format!(
+                        "<{}{}>{}</{}>",
+                        name,
+                        attributes.concat(),
+                        children.concat(),
+                        name
+                    )
+                })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn never_panics(input in prop_oneof![html(), any::<String>()]) {
+            let _ = safe_parse_html(input.clone());
+            let _ = safe_from_html_to_md(input.clone());
+            let _ = safe_parse_html_fragment(input.clone(), NodeType::Ul);
+            let _ = safe_parse_html_bytes(input.as_bytes());
+        }
+
+        #[test]
+        fn never_panics_on_bytes(input in any::<Vec<u8>>()) {
+            let _ = safe_parse_html_bytes(&input);
+        }
+
+        #[test]
+        fn lenient_parse_always_succeeds(input in html()) {
+            let config = ParseConfig {
+                lenient: true,
+                ..Default::default()
+            };
+            let parsed = safe_parse_html_with_config(input.clone(), &config);
+            prop_assert!(parsed.is_ok(), "{:?}", parsed);
+            to_md(parsed.unwrap());
+            parse_html_lenient(input);
+        }
+
+        #[test]
+        fn borrowed_matches_owned(input in html()) {
+            let owned = safe_parse_html(input.clone());
+            let borrowed = safe_parse_html_borrowed(&input).map(|node| node.into_owned());
+            prop_assert_eq!(borrowed, owned);
+        }
+
+        #[test]
+        fn streaming_matches_whole_input(input in html(), split in any::<proptest::sample::Index>()) {
+            let config = ParseConfig {
+                lenient: true,
+                ..Default::default()
+            };
+            let expected = safe_parse_html_with_config(input.clone(), &config).unwrap();
+            let bytes = input.as_bytes();
+            let split = split.index(bytes.len() + 1);
+            let mut parser = StreamingParser::with_config(config);
+            parser.feed(&bytes[..split]).unwrap();
+            parser.feed(&bytes[split..]).unwrap();
+            prop_assert_eq!(parser.finish().unwrap().root, expected);
+        }
+
+        #[test]
+        fn selectors_never_panic(selector in selector(), input in html()) {
+            if let (Ok(selector), Ok(mut node)) = (Selector::parse(&selector), safe_parse_html(input)) {
+                node.select(&selector);
+                node.select_mut(&selector);
+                node.remove_matching(&selector);
+            }
+        }
+
+        #[test]
+        fn round_trips_through_html(input in prop_oneof![html(), element()]) {
+            if let Ok(parsed) = safe_parse_html(input.clone()) {
+                let html = to_html(&parsed);
+                prop_assert_eq!(safe_parse_html(html.clone()), Ok(parsed), "{}", html);
+            }
+
+            // the malformed names read in the lenient mode (i.e. `=a` in `<p =a>`) are written back too
+            let (parsed, _) = parse_html_lenient(input);
+            let html = to_html(&parsed);
+            prop_assert_eq!(parse_html_lenient(html.clone()).0, parsed, "{}", html);
+        }
+    }
+}
@@ -0,0 +1,339 @@
+#[cfg(test)]
+mod selector_tests {
+    use html2md_rs::{
+        parser::{safe_parse_html, safe_parse_html_borrowed},
+        selector::{Selector, SelectorParseError},
+        structs::{AttributeValues, Node, NodeType},
+        to_html::to_html,
+    };
+
+    const PAGE: &str = "<div id=\"page\">\
+        <nav class=\"menu top\"><a href=\"/\">Home</a><a href=\"https://example.com/about\">About</a></nav>\
+        <article class=\"post post-body\" lang=\"en-US\">\
+        <h1>Title</h1>\
+        <p class=\"lead\">one</p>\
+        <!-- comment -->\
+        <p>two <a href=\"HTTPS://example.com/docs.PDF\">docs</a></p>\
+        <ul><li>a</li><li class=\"done\">b</li><li>c</li><li class=\"done\">d</li><li>e</li></ul>\
+        </article>\
+        <div class=\"ads\">buy</div>\
+        <footer><p>footer</p></footer>\
+        </div>";
+
+    fn page() -> Node {
+        safe_parse_html(PAGE.to_string()).unwrap()
+    }
+
+    /// Returns the HTML of the nodes matching the selector in the page
+    fn select(selector: &str) -> Vec<String> {
+        page()
+            .select(&Selector::parse(selector).unwrap())
+            .into_iter()
+            .map(to_html)
+            .collect()
+    }
+
+    /// Returns the texts of the nodes matching the selector in the page, which are the texts of their first text child
+    fn select_texts(selector: &str) -> Vec<String> {
+        page()
+            .select(&Selector::parse(selector).unwrap())
+            .into_iter()
+            .map(|node| {
+                node.children
+                    .iter()
+                    .find(|child| child.tag_name == Some(NodeType::Text))
+                    .and_then(|child| child.value.clone())
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn type_selectors() {
+        assert_eq!(select_texts("p"), vec!["one", "two ", "footer"]);
+        assert_eq!(select_texts("LI"), vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(select("*").len(), 18);
+        assert_eq!(select_texts("h1"), vec!["Title"]);
+    }
+
+    #[test]
+    fn class_and_id_selectors() {
+        assert_eq!(select_texts(".done"), vec!["b", "d"]);
+        assert_eq!(select_texts("li.done"), vec!["b", "d"]);
+        assert_eq!(select_texts("p.done"), Vec::<String>::new());
+        assert_eq!(select("article.post-body.post").len(), 1);
+        assert_eq!(select(".post-body.missing").len(), 0);
+        assert_eq!(select(".menu").len(), 1);
+        assert_eq!(select(".Menu").len(), 0);
+        assert_eq!(select("#page").len(), 1);
+        assert_eq!(select("div#page > .ads").len(), 1);
+    }
+
+    #[test]
+    fn attribute_selectors() {
+        assert_eq!(select_texts("[href]"), vec!["Home", "About", "docs"]);
+        assert_eq!(select_texts("a[href=\"/\"]"), vec!["Home"]);
+        assert_eq!(select_texts("a[href^=http]"), vec!["About"]);
+        assert_eq!(select_texts("a[href^=http i]"), vec!["About", "docs"]);
+        assert_eq!(select_texts("a[href$='.pdf' i]"), vec!["docs"]);
+        assert_eq!(select_texts("a[href$='.pdf']"), Vec::<String>::new());
+        assert_eq!(select_texts("a[href*=example]"), vec!["About", "docs"]);
+        assert_eq!(select("[class~=top]").len(), 1);
+        assert_eq!(select("[class~=to]").len(), 0);
+        assert_eq!(select("[class=menu]").len(), 0);
+        assert_eq!(select("[lang|=en]").len(), 1);
+        assert_eq!(select("[lang|=en-US]").len(), 1);
+        assert_eq!(select("[lang|=e]").len(), 0);
+        assert_eq!(select("[ID=page]").len(), 1);
+        assert_eq!(select("[href^='']").len(), 0);
+    }
+
+    #[test]
+    fn typed_attribute_values() {
+        let node = safe_parse_html(
+            "<form><ol start=\"3\"><li>a</li></ol><input type=checkbox checked><details open=\"\">x</details></form>"
+                .to_string(),
+        )
+        .unwrap();
+        let count = |selector: &str| node.select(&Selector::parse(selector).unwrap()).len();
+        assert_eq!(count("ol[start=\"3\"]"), 1);
+        assert_eq!(count("[checked]"), 1);
+        assert_eq!(count("[checked=\"\"]"), 1);
+        assert_eq!(count("[open]"), 1);
+        assert_eq!(count("[hidden]"), 0);
+    }
+
+    #[test]
+    fn combinators() {
+        assert_eq!(select_texts("article p"), vec!["one", "two "]);
+        assert_eq!(select_texts("div p"), vec!["one", "two ", "footer"]);
+        assert_eq!(select_texts("article > p"), vec!["one", "two "]);
+        assert_eq!(select_texts("#page > p"), Vec::<String>::new());
+        assert_eq!(select_texts("h1 + p"), vec!["one"]);
+        // the comment between the paragraphs is not a sibling element
+        assert_eq!(select_texts("p.lead + p"), vec!["two "]);
+        assert_eq!(select_texts("h1 ~ p"), vec!["one", "two "]);
+        assert_eq!(select_texts("li.done + li"), vec!["c", "e"]);
+        assert_eq!(select_texts("li.done ~ li"), vec!["c", "d", "e"]);
+        assert_eq!(select_texts("div article > ul li.done"), vec!["b", "d"]);
+        assert_eq!(select_texts("nav>a+a"), vec!["About"]);
+    }
+
+    #[test]
+    fn long_descendant_chains() {
+        // each compound selector is searched once from each element, instead of every way of picking the ancestors
+        let depth = 400;
+        let html = format!(
+            "{}<span>deep</span>{}",
+            "<div>".repeat(depth),
+            "</div>".repeat(depth)
+        );
+        let node = safe_parse_html(html).unwrap();
+        let chain = "div ".repeat(40);
+
+        let selector = Selector::parse(&format!("{}p", chain)).unwrap();
+        assert!(node.select(&selector).is_empty());
+        let selector = Selector::parse(&format!("{}div > p", chain)).unwrap();
+        assert!(node.select(&selector).is_empty());
+        let selector = Selector::parse(&format!("{}span", chain)).unwrap();
+        assert_eq!(node.select(&selector).len(), 1);
+        let selector = Selector::parse(&format!("{}div + span, p {}span", chain, chain)).unwrap();
+        assert!(node.select(&selector).is_empty());
+    }
+
+    #[test]
+    fn selector_lists() {
+        assert_eq!(select_texts("h1, .lead"), vec!["Title", "one"]);
+        // each node is matched once, in the order it appears in the HTML
+        assert_eq!(select_texts(".lead , p"), vec!["one", "two ", "footer"]);
+    }
+
+    #[test]
+    fn pseudo_classes() {
+        assert_eq!(select_texts("li:first-child"), vec!["a"]);
+        assert_eq!(select_texts("p:first-child"), vec!["footer"]);
+        assert_eq!(select_texts("li:nth-child(2)"), vec!["b"]);
+        assert_eq!(select_texts("li:nth-child(odd)"), vec!["a", "c", "e"]);
+        assert_eq!(select_texts("li:nth-child(even)"), vec!["b", "d"]);
+        assert_eq!(select_texts("li:nth-child(3n)"), vec!["c"]);
+        assert_eq!(select_texts("li:nth-child(2n+3)"), vec!["c", "e"]);
+        assert_eq!(select_texts("li:nth-child(-n + 2)"), vec!["a", "b"]);
+        assert_eq!(select_texts("li:nth-child(n+4)"), vec!["d", "e"]);
+        assert_eq!(select_texts("li:not(.done)"), vec!["a", "c", "e"]);
+        assert_eq!(select_texts("li:not(.done, :first-child)"), vec!["c", "e"]);
+        assert_eq!(select_texts("li:not(:not(.done))"), vec!["b", "d"]);
+        assert_eq!(select_texts("p:not(article > p)"), vec!["footer"]);
+    }
+
+    #[test]
+    fn select_first() {
+        let node = page();
+        let first = node.select_first(&Selector::parse("li.done").unwrap());
+        assert_eq!(
+            first.map(to_html),
+            Some("<li class=\"done\">b</li>".to_string())
+        );
+        assert!(node
+            .select_first(&Selector::parse("table").unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn matches_the_root() {
+        let node = safe_parse_html("<article><p>a</p></article>".to_string()).unwrap();
+        assert_eq!(node.select(&Selector::parse("article").unwrap()).len(), 1);
+        assert_eq!(
+            node.select(&Selector::parse("article:first-child > p").unwrap())
+                .len(),
+            1
+        );
+        assert!(Selector::parse("article").unwrap().matches(&node));
+        assert!(!Selector::parse("p").unwrap().matches(&node));
+
+        // the top-level nodes are siblings, but their root without a tag name is not an element
+        let node = safe_parse_html("<p>a</p><p>b</p>".to_string()).unwrap();
+        assert_eq!(node.select(&Selector::parse("p + p").unwrap()).len(), 1);
+        assert_eq!(node.select(&Selector::parse("* > p").unwrap()).len(), 0);
+    }
+
+    #[test]
+    fn foreign_elements() {
+        let node = safe_parse_html(
+            "<div><svg viewBox=\"0 0 1 1\"><linearGradient id=g></linearGradient></svg></div>"
+                .to_string(),
+        )
+        .unwrap();
+        let count = |selector: &str| node.select(&Selector::parse(selector).unwrap()).len();
+        assert_eq!(count("svg linearGradient"), 1);
+        assert_eq!(count("lineargradient"), 0);
+        assert_eq!(count("svg[viewBox]"), 1);
+        assert_eq!(count("svg[viewbox]"), 0);
+        assert_eq!(count("#g"), 1);
+    }
+
+    #[test]
+    fn escaped_names() {
+        let node =
+            safe_parse_html("<div class=\"md:flex\"><p class=\"a.b\">x</p></div>".to_string())
+                .unwrap();
+        assert_eq!(
+            node.select(&Selector::parse(".md\\:flex").unwrap()).len(),
+            1
+        );
+        assert_eq!(node.select(&Selector::parse(".a\\.b").unwrap()).len(), 1);
+        assert_eq!(
+            node.select(&Selector::parse("[class=\"a.b\"]").unwrap())
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn borrowed_nodes() {
+        let node = safe_parse_html_borrowed(PAGE).unwrap();
+        let selected = node.select(&Selector::parse("li.done").unwrap());
+        assert_eq!(selected.len(), 2);
+    }
+
+    #[test]
+    fn select_mut() {
+        let mut node = page();
+        let selector = Selector::parse("li:nth-child(odd), li:nth-child(odd) *").unwrap();
+        for li in node.select_mut(&selector) {
+            li.attributes
+                .get_or_insert_with(Default::default)
+                .insert("class".to_string(), AttributeValues::from("odd"));
+        }
+        assert_eq!(
+            to_html(node.select_first(&Selector::parse("ul").unwrap()).unwrap()),
+            "<ul><li class=\"odd\">a</li><li class=\"done\">b</li><li class=\"odd\">c</li><li class=\"done\">d</li><li class=\"odd\">e</li></ul>"
+        );
+
+        // the matches inside another match are reachable from it
+        let mut node =
+            safe_parse_html("<div><div><p>a</p></div></div><div>b</div>".to_string()).unwrap();
+        let mut selected = node.select_mut(&Selector::parse("div").unwrap());
+        assert_eq!(selected.len(), 2);
+        assert_eq!(selected[0].children.len(), 1);
+        selected[1].children.clear();
+        assert_eq!(to_html(&node), "<div><div><p>a</p></div></div><div></div>");
+    }
+
+    #[test]
+    fn remove_matching() {
+        let mut node = page();
+        let removed = node.remove_matching(&Selector::parse("nav, footer, .ads").unwrap());
+        assert_eq!(
+            removed
+                .iter()
+                .map(|node| node.tag_name.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                Some(NodeType::Unknown("nav".to_string())),
+                Some(NodeType::Div),
+                Some(NodeType::Unknown("footer".to_string())),
+            ]
+        );
+        assert_eq!(node.children.len(), 1);
+        assert!(node
+            .select_first(&Selector::parse("nav, footer, .ads").unwrap())
+            .is_none());
+
+        // the matches are found before any of them is removed
+        let mut node =
+            safe_parse_html("<ul><li>a</li><li>b<ul><li>c</li></ul></li></ul>".to_string())
+                .unwrap();
+        let removed = node.remove_matching(&Selector::parse("li:first-child").unwrap());
+        assert_eq!(removed.len(), 2);
+        assert_eq!(to_html(&node), "<ul><li>b<ul></ul></li></ul>");
+
+        // the matches inside another match are removed along with it
+        let mut node =
+            safe_parse_html("<ul><li>a<ul><li>b</li></ul></li><li>c</li></ul>".to_string())
+                .unwrap();
+        let removed = node.remove_matching(&Selector::parse("li").unwrap());
+        assert_eq!(
+            removed.iter().map(to_html).collect::<Vec<_>>(),
+            vec!["<li>a<ul><li>b</li></ul></li>", "<li>c</li>"]
+        );
+        assert_eq!(to_html(&node), "<ul></ul>");
+
+        // the node itself is never removed
+        let mut node = safe_parse_html("<div><div>a</div></div>".to_string()).unwrap();
+        let removed = node.remove_matching(&Selector::parse("div").unwrap());
+        assert_eq!(removed.len(), 1);
+        assert_eq!(to_html(&node), "<div></div>");
+    }
+
+    #[test]
+    fn parse_errors() {
+        use SelectorParseError::*;
+        assert_eq!(Selector::parse(""), Err(UnexpectedEnd(0)));
+        assert_eq!(Selector::parse("ul >"), Err(UnexpectedEnd(4)));
+        assert_eq!(Selector::parse("ul >> li"), Err(UnexpectedCharacter(4)));
+        assert_eq!(Selector::parse("a,,b"), Err(UnexpectedCharacter(2)));
+        assert_eq!(Selector::parse("a)"), Err(UnexpectedCharacter(1)));
+        assert_eq!(Selector::parse("[href"), Err(UnexpectedEnd(5)));
+        assert_eq!(Selector::parse("[href!=a]"), Err(UnexpectedCharacter(5)));
+        assert_eq!(Selector::parse("[href='a]"), Err(UnexpectedEnd(9)));
+        assert_eq!(Selector::parse("[href=a x]"), Err(UnexpectedCharacter(8)));
+        assert_eq!(Selector::parse("a:hover"), Err(UnsupportedPseudoClass(1)));
+        assert_eq!(Selector::parse("li:not(.a"), Err(UnexpectedEnd(9)));
+        assert_eq!(Selector::parse("li:nth-child(x)"), Err(InvalidNthChild(13)));
+        assert_eq!(
+            Selector::parse("li:nth-child(2n+)"),
+            Err(InvalidNthChild(13))
+        );
+        assert_eq!(Selector::parse("li:nth-child(2"), Err(UnexpectedEnd(14)));
+        assert_eq!(Selector::parse("1a"), Err(UnexpectedCharacter(0)));
+
+        let nested = format!("{}a{}", ":not(".repeat(100), ")".repeat(100));
+        assert_eq!(Selector::parse(&nested), Err(TooDeeplyNested(160)));
+
+        assert_eq!(
+            Selector::parse("a:hover").unwrap_err().to_string(),
+            "Unsupported pseudo-class at offset 1 of the selector"
+        );
+        assert_eq!("li.done".parse(), Selector::parse("li.done"));
+    }
+}
@@ -0,0 +1,262 @@
+#[cfg(test)]
+mod to_html_tests {
+    use html2md_rs::{
+        parser::{parse_html_lenient, safe_parse_html},
+        structs::{AttributeValues, Attributes, Node, NodeType},
+        to_html::{attributes_to_html, safe_from_html_to_html, to_html},
+    };
+
+    /// Asserts that the HTML is serialized as expected, and that parsing the serialized HTML gives the same Node
+    fn assert_round_trip(input: &str, expected: &str) {
+        let parsed = safe_parse_html(input.to_string()).unwrap();
+        let html = to_html(&parsed);
+        assert_eq!(html, expected);
+        assert_eq!(safe_parse_html(html).unwrap(), parsed);
+    }
+
+    #[test]
+    fn simple_elements() {
+        assert_round_trip("<h1>Hello world</h1>", "<h1>Hello world</h1>");
+        assert_round_trip(
+            "<div><p>one</p><p>two <b>three</b></p></div>",
+            "<div><p>one</p><p>two <b>three</b></p></div>",
+        );
+    }
+
+    #[test]
+    fn top_level_nodes() {
+        assert_round_trip("<p>one</p>two<p>three</p>", "<p>one</p>two<p>three</p>");
+    }
+
+    #[test]
+    fn implied_closing_tags() {
+        assert_round_trip(
+            "<ul><li>one<li>two</ul><p>three",
+            "<ul><li>one</li><li>two</li></ul><p>three</p>",
+        );
+    }
+
+    #[test]
+    fn escaped_text() {
+        assert_round_trip(
+            "<p>1 &lt; 2 &amp;&amp; 3 &gt; 2&nbsp;&quot;ok&quot;</p>",
+            "<p>1 &lt; 2 &amp;&amp; 3 &gt; 2&nbsp;\"ok\"</p>",
+        );
+        assert_round_trip("<p>a & b</p>", "<p>a &amp; b</p>");
+    }
+
+    #[test]
+    fn escaped_attributes() {
+        assert_round_trip(
+            "<a href='/search?q=\"rust\"&amp;page=2' title=\"a<b>\">link</a>",
+            "<a href=\"/search?q=&quot;rust&quot;&amp;page=2\" title=\"a&lt;b&gt;\">link</a>",
+        );
+    }
+
+    #[test]
+    fn unusual_names() {
+        // quotation marks and equal signs are a part of the names, which are written as they are
+        assert_round_trip(
+            "<a\"b c'=\"'\" d<>x</a\"b><x=y>z</x=y>",
+            "<a\"b c'=\"'\" d<>x</a\"b><x=y>z</x=y>",
+        );
+
+        // a name starting with an equal sign is separated from a boolean attribute before it
+        let (parsed, _) = parse_html_lenient("<p &/=b>x</p>".to_string());
+        let html = to_html(&parsed);
+        assert_eq!(html, "<p & /=b>x</p>");
+        assert_eq!(parse_html_lenient(html).0, parsed);
+
+        // a quotation mark after it doesn't start a value
+        let (parsed, _) = parse_html_lenient("<p =\">x</p>".to_string());
+        let html = to_html(&parsed);
+        assert_eq!(html, "<p =\">x</p>");
+        assert_eq!(parse_html_lenient(html).0, parsed);
+    }
+
+    #[test]
+    fn invalid_names() {
+        // the elements and attributes whose names can't be written are left out, so they can't inject markup
+        let text = Node::new(
+            Some(NodeType::Text),
+            Some("x".to_string()),
+            None,
+            None,
+            vec![],
+        );
+        let attributes = Attributes::from(vec![
+            ("onclick=alert(1) a".to_string(), AttributeValues::from("b")),
+            ("c>".to_string(), AttributeValues::from(true)),
+            ("d/e".to_string(), AttributeValues::from("f")),
+            ("g=h".to_string(), AttributeValues::from("i")),
+            ("\u{e9}=".to_string(), AttributeValues::from("j")),
+            ("k".to_string(), AttributeValues::from("l")),
+        ]);
+        let node = Node::new(
+            Some(NodeType::Unknown("img src=x onerror=alert(1)".to_string())),
+            None,
+            None,
+            None,
+            vec![
+                Node::new(
+                    Some(NodeType::P),
+                    None,
+                    Some(attributes),
+                    None,
+                    vec![text.clone()],
+                ),
+                Node::new(
+                    Some(NodeType::Unknown("!--".to_string())),
+                    None,
+                    None,
+                    None,
+                    vec![text],
+                ),
+            ],
+        );
+        assert_eq!(to_html(&node), "<p k=\"l\">x</p>x");
+    }
+
+    #[test]
+    fn attribute_order() {
+        assert_round_trip(
+            "<div title=t data-b=b class=c data-a=a id=i>x</div>",
+            "<div id=\"i\" class=\"c\" data-a=\"a\" data-b=\"b\" title=\"t\">x</div>",
+        );
+    }
+
+    #[test]
+    fn typed_attributes() {
+        assert_round_trip(
+            "<ol start=\"3\"><li><input type=checkbox checked=\"checked\" disabled>one</li></ol>",
+            "<ol start=\"3\"><li><input checked disabled type=\"checkbox\">one</li></ol>",
+        );
+
+        let attributes = Attributes::from(vec![
+            ("open".to_string(), AttributeValues::from(false)),
+            ("width".to_string(), AttributeValues::from(-1)),
+        ]);
+        assert_eq!(attributes_to_html(&attributes), "width=\"-1\"");
+        assert_eq!(attributes_to_html(&Attributes::new()), "");
+    }
+
+    #[test]
+    fn void_elements() {
+        assert_round_trip(
+            "<p>one<br/>two<hr><img src=a.png alt=\"\"></p>",
+            "<p>one<br>two</p><hr><img alt=\"\" src=\"a.png\">",
+        );
+        assert_round_trip(
+            "<head><meta charset=utf-8><link rel=icon href=/icon.png></head>",
+            "<head><meta charset=\"utf-8\"><link href=\"/icon.png\" rel=\"icon\"></head>",
+        );
+    }
+
+    #[test]
+    fn comments() {
+        assert_round_trip(
+            "<div><!-- a comment --><p>text</p><!----></div>",
+            "<div><!-- a comment --><p>text</p><!----></div>",
+        );
+        assert_round_trip("<p><!-- <b> & </b> -->a</p>", "<p><!-- <b> & </b> -->a</p>");
+    }
+
+    #[test]
+    fn raw_text_elements() {
+        assert_round_trip(
+            "<script>if (a < b && c) {}</script><style>a > b {}</style>",
+            "<script>if (a < b && c) {}</script><style>a > b {}</style>",
+        );
+        assert_round_trip(
+            "<textarea>a < b &amp; c</textarea>",
+            "<textarea>a &lt; b &amp; c</textarea>",
+        );
+        assert_round_trip(
+            "<div><plaintext><p>a</p></div>",
+            "<div><plaintext><p>a</p></div>",
+        );
+    }
+
+    #[test]
+    fn preformatted_text() {
+        assert_round_trip(
+            "<pre><code>fn main() {\n    println!(\"&lt;hi&gt;\");\n}</code></pre>",
+            "<pre><code>fn main() {\n    println!(\"&lt;hi&gt;\");\n}</code></pre>",
+        );
+    }
+
+    #[test]
+    fn foreign_elements() {
+        assert_round_trip(
+            "<svg viewbox=\"0 0 10 10\"><lineargradient id=\"g\"/><path d=\"M0 0\"/><style>a &lt; b</style></svg>",
+            "<svg viewBox=\"0 0 10 10\"><linearGradient id=\"g\"></linearGradient><path d=\"M0 0\"></path><style>a &lt; b</style></svg>",
+        );
+        assert_round_trip(
+            "<math><mi>x</mi><mo>&lt;</mo><mn>2</mn></math>",
+            "<math><mi>x</mi><mo>&lt;</mo><mn>2</mn></math>",
+        );
+    }
+
+    #[test]
+    fn constructed_nodes() {
+        let node = Node::new(
+            Some(NodeType::Unknown("my-card".to_string())),
+            None,
+            Some(Attributes::from(vec![(
+                "data-x".to_string(),
+                AttributeValues::from("1"),
+            )])),
+            None,
+            vec![
+                Node::new(
+                    Some(NodeType::Text),
+                    Some("a\u{A0}b".to_string()),
+                    None,
+                    None,
+                    vec![],
+                ),
+                Node::new(
+                    Some(NodeType::Comment),
+                    Some(" c ".to_string()),
+                    None,
+                    None,
+                    vec![],
+                ),
+            ],
+        );
+        assert_eq!(
+            to_html(&node),
+            "<my-card data-x=\"1\">a&nbsp;b<!-- c --></my-card>"
+        );
+    }
+
+    #[test]
+    fn normalizes_html() {
+        assert_eq!(
+            safe_from_html_to_html("<P CLASS=a>one<B>two</p>".to_string()),
+            Ok("<p class=\"a\">one<b>two</b></p>".to_string())
+        );
+        assert!(safe_from_html_to_html("<p".to_string()).is_err());
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let depth = 300_000;
+        let mut node = Node::new(
+            Some(NodeType::Text),
+            Some("x".to_string()),
+            None,
+            None,
+            vec![],
+        );
+        for _ in 0..depth {
+            node = Node::new(Some(NodeType::Div), None, None, None, vec![node]);
+        }
+        let html = to_html(&node);
+        assert_eq!(html.len(), depth * "<div></div>".len() + 1);
+        let mut nodes = vec![node];
+        while let Some(mut node) = nodes.pop() {
+            nodes.append(&mut node.children);
+        }
+    }
+}
@@ -1,9 +1,16 @@
 #[cfg(test)]
 mod to_md_tests {
     use html2md_rs::{
-        parser::safe_parse_html,
-        structs::{Node, NodeType, ToMdConfig},
-        to_md::{safe_from_html_to_md, safe_from_html_to_md_with_config},
+        parser::{
+            parse_html_lenient, safe_parse_html, safe_parse_html_borrowed,
+            safe_parse_html_borrowed_with_config, safe_parse_html_fragment,
+            safe_parse_html_with_config, ParseHTMLError,
+        },
+        structs::{AttributeValues, Attributes, Limits, Node, NodeType, ParseConfig, ToMdConfig},
+        to_md::{
+            safe_from_html_to_md, safe_from_html_to_md_checked, safe_from_html_to_md_with_config,
+            safe_to_md_with_config, to_md, to_md_with_config, OutputTooLongError, ToMdError,
+        },
     };
 
     pub trait PrintNode {
@@ -173,6 +180,27 @@ println!(\"{}\", z);
         let input = "<ol start=\"3\"><li><p>hello</p></li><li><p>world</p></li></ol>".to_string();
         let expected = "3. hello\n4. world\n".to_string();
         assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+
+        // a hand-built node can hold the start as a string
+        let item = |value: &str| Node {
+            tag_name: Some(NodeType::Li),
+            children: vec![Node {
+                tag_name: Some(NodeType::Text),
+                value: Some(value.to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let input = Node {
+            tag_name: Some(NodeType::Ol),
+            attributes: Some(Attributes::from(vec![(
+                "start".to_string(),
+                AttributeValues::String(" 3 ".to_string()),
+            )])),
+            children: vec![item("hello"), item("world")],
+            ..Default::default()
+        };
+        assert_eq!(to_md(input), "3. hello\n4. world\n");
     }
 
     #[test]
@@ -184,8 +212,9 @@ println!(\"{}\", z);
 
     #[test]
     fn comments_in_p() {
+        // the comment is inside the paragraph, which ends the line
         let input = "<p><!-- hello --></p>".to_string();
-        let expected = "<!-- hello -->".to_string();
+        let expected = "<!-- hello -->\n".to_string();
         assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
     }
 
@@ -208,13 +237,298 @@ println!(\"{}\", z);
     fn ignore_rendering() {
         let input =
             "<div><span>don't render this</span><p>this should be rendered</p><div>render this</div></div>".to_string();
-        let config = ToMdConfig {
-            ignore_rendering: vec![NodeType::Unknown("span".to_string())],
-        };
+        let config = ToMdConfig::default()
+            .with_ignore_rendering(vec![NodeType::Unknown("span".to_string())]);
         let expected = "this should be rendered\nrender this".to_string();
         assert_eq!(
             safe_from_html_to_md_with_config(input, &config).unwrap(),
             expected
         );
     }
+
+    #[test]
+    fn character_references() {
+        let input = "<p>Tom &amp; Jerry&#8217;s &lt;show&gt;</p>".to_string();
+        let expected = "Tom & Jerry\u{2019}s <show>\n".to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn escaped_text() {
+        let input = "<p>&lt;img src=x onerror=alert(1)&gt;</p>".to_string();
+        let expected = "&lt;img src=x onerror=alert(1)>\n".to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+
+        // `&` and `<` are only escaped when they would be read as a character reference or markup
+        let input = "<p>&lt;script&gt;alert(1)&lt;/script&gt; &lt;javascript:x&gt;</p><p>AT&amp;T &amp;amp; 1 &lt; 2 &gt; 0 &lt;3</p>"
+            .to_string();
+        let expected =
+            "&lt;script>alert(1)&lt;/script> &lt;javascript:x>\nAT&T &amp;amp; 1 < 2 > 0 <3\n"
+                .to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+
+        let input = "<p>&gt; not a quote</p>".to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), "\\> not a quote\n");
+
+        let input = "<p>*not bold* and [not](a link) 2_000_000</p><p># not a heading</p><p>- not a list</p>"
+            .to_string();
+        let expected = "\\*not bold\\* and \\[not\\](a link) 2\\_000\\_000\n\\# not a heading\n\\- not a list\n"
+            .to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+
+        // the texts of code are written as they are
+        let input = "<pre><code>if a &lt; b &amp;&amp; *c {}</code></pre><p>*a*</p>".to_string();
+        let expected = "```\nif a < b && *c {}```\n\\*a\\*\n".to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn link_with_trailing_slash() {
+        let input = "<p><a href=http://x.com/>link</a></p>".to_string();
+        let expected = "[link](http://x.com/)\n".to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn void_elements_without_trailing_slash() {
+        let input = "<p>hello<br>world</p><hr><p>after</p>".to_string();
+        let expected = "hello  \nworld\n***\nafter\n".to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn mis_nested_closing_tags() {
+        let input = "<p><strong>hello</p><p>world</strong></p></em>".to_string();
+        let expected = "**hello**\n**world**\n".to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn implicit_end_tags() {
+        let input = "<ul><li>one<li>two</ul><ol><li>three<li>four</ol><p>five<p>six".to_string();
+        let expected = "- one\n- two\n1. three\n2. four\nfive\nsix\n".to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn raw_text_elements() {
+        let input = "<html><head><title>a < b</title><style>p > a { color: red; }</style></head><body><p>hello</p><script>if (a < b) { document.write(\"<p>\"); }</script></body></html>".to_string();
+        let expected =
+            "p > a { color: red; }hello\nif (a < b) { document.write(\"<p>\"); }".to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn xhtml_prolog_and_cdata() {
+        let input = "<?xml version=\"1.0\"?><html xmlns=\"http://www.w3.org/1999/xhtml\"><body><p><![CDATA[1 < 2]]></p></body></html>".to_string();
+        let expected = "1 < 2\n".to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn lowercase_doctype() {
+        let input = "<!doctype html><html><body><h1>hello</h1></body></html>".to_string();
+        let expected = "# hello\n".to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn lenient_parse() {
+        let input = "<p>1 < 2 & 3 > 2</p><p>cut off <a href=\"https://example.com".to_string();
+        let (parsed, warnings) = parse_html_lenient(input);
+        let expected = "1 < 2 & 3 > 2\ncut off\n".to_string();
+        assert_eq!(to_md(parsed), expected);
+        assert_eq!(warnings.len(), 2);
+    }
+
+    #[test]
+    fn borrowed_node() {
+        let input = "<h1>hello</h1><ul><li>Tom &amp; Jerry</li><li><!-- world --></li></ul>";
+        let expected = "# hello\n- Tom & Jerry\n- <!-- world -->\n".to_string();
+        assert_eq!(to_md(safe_parse_html_borrowed(input).unwrap()), expected);
+        assert_eq!(to_md(safe_parse_html(input.to_string()).unwrap()), expected);
+    }
+
+    #[test]
+    fn collapsed_whitespace() {
+        let input = "<h1>\n  hello\n  world\n</h1>\n<p>\n  <strong>a</strong> <em>b</em>\n</p>\n<pre><code>fn main() {\n    let x;\n}\n</code></pre>".to_string();
+        let expected =
+            "# hello world\n**a** *b*\n```\nfn main() {\n    let x;\n}\n```\n".to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn fragment() {
+        let input = "<li>one</li><li><p>two</p><ul><li>three</li></ul></li>".to_string();
+        let expected = "- one\n- two\n  - three\n".to_string();
+        assert_eq!(
+            to_md(safe_parse_html_fragment(input, NodeType::Ul).unwrap()),
+            expected
+        );
+
+        let input = "<p>hello</p>".to_string();
+        let expected = "> hello\n".to_string();
+        assert_eq!(
+            to_md(safe_parse_html_fragment(input, NodeType::Blockquote).unwrap()),
+            expected
+        );
+    }
+
+    #[test]
+    fn foreign_content() {
+        let input = "<p>icon<svg viewBox=\"0 0 10 10\"><text>hidden</text></svg> and <math><mi>x</mi><mo>+</mo><mn>1</mn><annotation encoding=\"TeX\">x+1</annotation></math></p>".to_string();
+        let expected = "icon and x+1\n".to_string();
+        assert_eq!(safe_from_html_to_md(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn output_length_limit() {
+        let input = "<ul><li>caf\u{e9}</li><li>two</li><li>three</li></ul>".to_string();
+        let node = safe_parse_html(input).unwrap();
+        let config = ToMdConfig::default().with_max_output_length(6);
+        // the markdown is cut at a character boundary
+        assert_eq!(to_md_with_config(node.clone(), &config), "- caf");
+        assert_eq!(
+            safe_to_md_with_config(node.clone(), &config),
+            Err(OutputTooLongError {
+                max_output_length: 6
+            })
+        );
+
+        let config = ToMdConfig::default().with_max_output_length(22);
+        assert_eq!(
+            safe_to_md_with_config(node, &config),
+            Ok("- caf\u{e9}\n- two\n- three\n".to_string())
+        );
+
+        // the checked conversion of a string of HTML reports the limit instead of cutting the markdown off
+        let input = "<p>Tom &amp; Jerry</p>".to_string();
+        let config = ToMdConfig::default().with_max_output_length(11);
+        let error = safe_from_html_to_md_checked(input.clone(), &config).unwrap_err();
+        assert_eq!(
+            error,
+            ToMdError::OutputTooLong(OutputTooLongError {
+                max_output_length: 11
+            })
+        );
+        assert_eq!(
+            error.to_string(),
+            "Markdown longer than the limit of 11 bytes"
+        );
+        assert_eq!(
+            safe_from_html_to_md_with_config(input, &config),
+            Ok("Tom & Jerry".to_string())
+        );
+        let error = safe_from_html_to_md_checked("<p".to_string(), &config).unwrap_err();
+        assert!(matches!(
+            error,
+            ToMdError::Parse(ParseHTMLError::MalformedTag(..))
+        ));
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let depth = 300_000;
+        let text = |value: &str| Node {
+            tag_name: Some(NodeType::Text),
+            value: Some(value.to_string()),
+            ..Default::default()
+        };
+
+        let mut node = text("deep");
+        for _ in 0..depth {
+            node = Node {
+                tag_name: Some(NodeType::Div),
+                children: vec![node],
+                ..Default::default()
+            };
+        }
+        assert_eq!(to_md(node), "deep");
+
+        let mut node = text("item");
+        for _ in 0..depth / 2 {
+            let li = Node {
+                tag_name: Some(NodeType::Li),
+                children: vec![node],
+                ..Default::default()
+            };
+            node = Node {
+                tag_name: Some(NodeType::Ul),
+                children: vec![li],
+                ..Default::default()
+            };
+        }
+        let expected = format!("{}item{}", "- ".repeat(depth / 2), "\n".repeat(depth / 2));
+        let config = ToMdConfig::default().with_max_output_length(usize::MAX);
+        assert_eq!(safe_to_md_with_config(node, &config), Ok(expected));
+    }
+
+    #[test]
+    fn deeply_nested_html() {
+        // each start tag checks for an open <p> or <a> in constant time, so parsing deep HTML takes linear time
+        let depth = 100_000;
+        let input = format!(
+            "{}deep{}",
+            "<div><p>x</p><a href=y>z</a>".repeat(depth),
+            "</div>".repeat(depth)
+        );
+        let config = ParseConfig {
+            limits: Limits::unlimited(),
+            ..Default::default()
+        };
+        let node = safe_parse_html_borrowed_with_config(&input, &config).unwrap();
+        let markdown = to_md(node);
+        assert!(markdown.starts_with("x\n[z](y)x\n[z](y)"));
+        assert!(markdown.ends_with("[z](y)deep"));
+    }
+
+    #[test]
+    #[ignore = "a benchmark, run with `cargo test --release -- --ignored`"]
+    fn deeply_nested_html_in_linear_time() {
+        let depth = 300_000;
+        let input = format!(
+            "{}deep{}",
+            "<div><p>x</p><a href=y>z</a>".repeat(depth),
+            "</div>".repeat(depth)
+        );
+        let config = ParseConfig {
+            limits: Limits::unlimited(),
+            ..Default::default()
+        };
+        let start = std::time::Instant::now();
+        let node = safe_parse_html_borrowed_with_config(&input, &config).unwrap();
+        to_md(node);
+        assert!(
+            start.elapsed() < std::time::Duration::from_secs(10),
+            "took {:?}",
+            start.elapsed()
+        );
+    }
+
+    #[test]
+    fn deeply_nested_owned_node() {
+        let depth = 200_000;
+        let input = format!("{}deep{}", "<div>".repeat(depth), "</div>".repeat(depth));
+        let config = ParseConfig {
+            limits: Limits::unlimited(),
+            ..Default::default()
+        };
+
+        let node = safe_parse_html_borrowed_with_config(&input, &config)
+            .unwrap()
+            .into_owned();
+        // the outermost <div> is the root node
+        let mut deepest = &node;
+        let mut divs = 1;
+        while let [child] = deepest.children.as_slice() {
+            divs += usize::from(child.tag_name == Some(NodeType::Div));
+            deepest = child;
+        }
+        assert_eq!(divs, depth);
+        assert_eq!(deepest.value.as_deref(), Some("deep"));
+        node.drop_iteratively();
+
+        let node = safe_parse_html_with_config(input, &config).unwrap();
+        node.drop_iteratively();
+    }
 }